@@ -0,0 +1,49 @@
+/*
+ * Benchmarks of the cycle detection methods of detect_cycle_with, on a
+ * contrived rule which cycles since no real configuration does.
+ * Run with "cargo bench --bench cycles".
+ */
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use density_checker::{Configuration, CycleDetection, Rule};
+
+const TAIL : u32 = 1_000;
+const PERIOD : u32 = 100_000;
+
+/**
+ * Counts the value up from 0, and sends it back to TAIL once it reaches
+ * TAIL + PERIOD, so the executions never converge.
+ */
+struct Counter;
+
+impl Rule for Counter {
+    const NAME : &'static str = "counter";
+
+    fn update(x : &mut Configuration) {
+        x.value = if x.value + 1 == TAIL + PERIOD { TAIL } else { x.value + 1 };
+    }
+
+    fn has_converged(_ : &Configuration) -> bool {
+        false
+    }
+}
+
+fn cycles(c : &mut Criterion) {
+    let mut group = c.benchmark_group("detect_cycle");
+    // each run goes around a cycle of PERIOD states
+    group.sample_size(10);
+
+    for method in [CycleDetection::StoreStates, CycleDetection::Brent] {
+        group.bench_with_input(BenchmarkId::from_parameter(format!("{method:?}")), &method, |b, &method| {
+            b.iter(|| {
+                let info = Configuration::new(0, 31).detect_cycle_with::<Counter>(u32::MAX, method);
+                assert_eq!((info.tail, info.period), (TAIL, PERIOD));
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, cycles);
+criterion_main!(benches);
@@ -0,0 +1,53 @@
+/*
+ * Benchmarks of the bit manipulation helpers of the local function,
+ * for each width of number a configuration could be backed by.
+ * Run with "cargo bench".
+ */
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+#[path = "../src/bits.rs"]
+mod bits;
+
+use bits::{assign_bool, self_assign, Bits};
+
+/**
+ * Shifts a pattern along a whole word one bit at a time, the same way
+ * the local function copies flags from the left cell during a sweep.
+ */
+fn sweep_self_assign<W : Bits>(mut mem : W) -> W {
+    for k in 1..W::BITS {
+        self_assign(&mut mem, k, k - 1);
+    }
+    mem
+}
+
+/**
+ * Writes an alternating pattern on a whole word one bit at a time.
+ */
+fn sweep_assign_bool<W : Bits>(mut to : W) -> W {
+    for k in 0..W::BITS {
+        assign_bool(&mut to, k, k % 2 == 0);
+    }
+    to
+}
+
+fn bench_width<W : Bits>(c : &mut Criterion, name : &str, seed : W) {
+    let mut group = c.benchmark_group("helpers");
+
+    group.bench_with_input(BenchmarkId::new("self_assign", name), &seed,
+        |b, &seed| b.iter(|| sweep_self_assign(black_box(seed))));
+    group.bench_with_input(BenchmarkId::new("assign_bool", name), &seed,
+        |b, &seed| b.iter(|| sweep_assign_bool(black_box(seed))));
+
+    group.finish();
+}
+
+fn helpers(c : &mut Criterion) {
+    bench_width(c, "u32", 0x9E37_79B9u32);
+    bench_width(c, "u64", 0x9E37_79B9_7F4A_7C15u64);
+    bench_width(c, "u128", 0x9E37_79B9_7F4A_7C15_F39C_C060_5CED_C834u128);
+}
+
+criterion_group!(benches, helpers);
+criterion_main!(benches);
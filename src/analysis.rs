@@ -0,0 +1,412 @@
+/*
+ * The analyses of the executions beyond the verdict of each configuration :
+ * the failures by density and the ties, the histograms of the steps and
+ * traversals, the predictions of the results, the sensitivity to single
+ * cell changes, the timings of the searches and the checksums of the
+ * traces.
+ */
+
+#[cfg(feature = "search")]
+use std::io::{self, Write};
+
+#[cfg(feature = "search")]
+use rand::{rngs::StdRng, Rng, SeedableRng};
+#[cfg(feature = "search")]
+use rayon::iter::{IntoParallelIterator, ParallelBridge, ParallelIterator};
+
+use crate::{Configuration, EqualityStrategy};
+#[cfg(feature = "search")]
+use crate::{
+    all_configurations, expected_convergence_bound, find_counter_example, SearchOptions, TiePolicy,
+    Verdict,
+};
+
+/**
+ * Iterates in increasing order over all the numbers below 1 << size
+ * which have exactly a given number of 1 bits, using Gosper's hack to
+ * jump from one to the next.
+ */
+#[cfg(feature = "search")]
+pub(crate) struct FixedPopcount {
+    next : Option<u64>,
+    limit : u64,
+}
+
+#[cfg(feature = "search")]
+impl FixedPopcount {
+    pub(crate) fn new(size : u32, ones : u32) -> Self {
+        let first = (1u64 << ones) - 1;
+        let limit = 1u64 << size;
+        Self { next : if first < limit { Some(first) } else { None }, limit }
+    }
+}
+
+#[cfg(feature = "search")]
+impl Iterator for FixedPopcount {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        let current = self.next?;
+
+        self.next = if current == 0 {
+            None // only one number has no 1 bit
+        }
+        else {
+            // Gosper's hack : move the lowest block of 1s up by one and
+            // pack the remaining bits of the block at the bottom
+            let lowest = current & current.wrapping_neg();
+            let ripple = current + lowest;
+            let next = (((ripple ^ current) >> 2) / lowest) | ripple;
+            if next < self.limit { Some(next) } else { None }
+        };
+
+        Some(current as u32)
+    }
+}
+
+/**
+ * The reasons why find_counter_example_at_density has nothing to search.
+ */
+#[derive(Debug, PartialEq, Eq)]
+pub enum DensityError {
+    // There are more ones than cells.
+    TooManyOnes { size : u32, ones : u32 },
+    // As many 0s as 1s : the ties have no majority to converge to, and
+    // are always considered correct, so they are skipped.
+    Tie,
+}
+
+impl std::error::Error for DensityError {}
+
+impl std::fmt::Display for DensityError {
+    fn fmt(&self, f : &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::TooManyOnes { size, ones } => write!(f, "cannot have {ones} ones in a configuration of size {size}"),
+            Self::Tie => write!(f, "ties have no majority, their verdict is undefined"),
+        }
+    }
+}
+
+/**
+ * Same as find_counter_example, but only iterates through the configurations
+ * of the given size with exactly `ones` cells at 1. Configurations with as
+ * many 0s as 1s have an undefined verdict, and are not searched.
+ *
+ * Unlike find_counter_example, both a configuration and its complement are
+ * tested, as they don't have the same number of 1s.
+ */
+#[cfg(feature = "search")]
+pub fn find_counter_example_at_density(size : u32, ones : u32) -> Result<Option<u32>, DensityError> {
+    if ones > size {
+        return Err(DensityError::TooManyOnes { size, ones });
+    }
+    if 2 * ones == size {
+        return Err(DensityError::Tie);
+    }
+
+    Ok(FixedPopcount::new(size, ones)
+        .par_bridge()
+        .find_any(|&k| ! Configuration::new(k, size).is_correct()))
+}
+
+/**
+ * Checks every tie of the given size (as many 0s as 1s) against the given
+ * convention, and returns how many of them converged to the value it
+ * chooses, along with the number of ties. Odd sizes have no ties.
+ */
+#[cfg(feature = "search")]
+pub fn check_ties(size : u32, convention : bool) -> (u64, u64) {
+    if ! size.is_multiple_of(2) {
+        return (0, 0);
+    }
+
+    FixedPopcount::new(size, size / 2)
+        .par_bridge()
+        .map(|k| (Configuration::new(k, size).is_correct_with_ties(TiePolicy::ConvergeTo(convention)) as u64, 1))
+        .reduce(|| (0, 0), |a, b| (a.0 + b.0, a.1 + b.1))
+}
+
+/**
+ * Counts, for each possible number of 1s, how many configurations of the
+ * given size with that many 1s are counter examples. Returns the
+ * (ones, failures) pairs in increasing number of 1s. For even sizes, the
+ * tie class (size / 2 ones) is left out, since its configurations are
+ * always considered correct.
+ */
+#[cfg(feature = "search")]
+pub fn failure_histogram_by_density(size : u32) -> Vec<(u32, u64)> {
+    (0..=size)
+        .filter(|&ones| 2 * ones != size)
+        .map(|ones| {
+            let failures = FixedPopcount::new(size, ones)
+                .par_bridge()
+                .filter(|&k| ! Configuration::new(k, size).is_correct())
+                .count();
+            (ones, failures as u64)
+        })
+        .collect()
+}
+
+/**
+ * Writes the result of failure_histogram_by_density as a table. Returns
+ * true if no density has any failure.
+ */
+#[cfg(feature = "search")]
+pub fn write_failure_histogram(size : u32, w : &mut dyn Write) -> io::Result<bool> {
+    let mut clean = true;
+    writeln!(w, "ones   failures")?;
+    for (ones, failures) in failure_histogram_by_density(size) {
+        writeln!(w, "{ones:4} {failures:10}")?;
+        clean &= failures == 0;
+    }
+    if size.is_multiple_of(2) {
+        writeln!(w, "(ties with {} ones skipped)", size / 2)?;
+    }
+
+    Ok(clean)
+}
+
+/**
+ * Summarizes the executions of all the configurations of a given size, in
+ * parallel, and counts how many take each number of updates and how many
+ * need each number of traversals of the head. Index n of the first vector
+ * is the number of configurations converging after n updates, and index n
+ * of the second the number of configurations whose head completes n
+ * traversals. Configurations which do not converge are left out.
+ */
+#[cfg(feature = "search")]
+fn step_and_traversal_histograms(size : u32) -> (Vec<u64>, Vec<u64>) {
+    let length = expected_convergence_bound(size) as usize + 1;
+
+    all_configurations(size)
+        .fold(
+            || (vec![0u64; length], vec![0u64; length]),
+            |(mut steps, mut traversals), mut x| {
+                let summary = x.summarize();
+                if summary.value.is_some() {
+                    steps[summary.steps as usize] += 1;
+                    traversals[summary.traversals as usize] += 1;
+                }
+                (steps, traversals)
+            })
+        .reduce(
+            || (vec![0u64; length], vec![0u64; length]),
+            |(mut steps, mut traversals), (other_steps, other_traversals)| {
+                for (a, b) in steps.iter_mut().zip(other_steps) { *a += b; }
+                for (a, b) in traversals.iter_mut().zip(other_traversals) { *a += b; }
+                (steps, traversals)
+            })
+}
+
+/**
+ * Writes the result of step_and_traversal_histograms as a table : for each
+ * count, the number of configurations taking that many updates, and the
+ * number of configurations needing that many traversals. Only the
+ * configurations whose last cell is a 0 are counted, the others behaving
+ * the same way as their complements.
+ */
+#[cfg(feature = "search")]
+pub fn write_traversal_histogram(size : u32, w : &mut dyn Write) -> io::Result<()> {
+    let (steps, traversals) = step_and_traversal_histograms(size);
+
+    writeln!(w, "count      steps traversals")?;
+    for (count, (steps, traversals)) in steps.iter().zip(&traversals).enumerate() {
+        writeln!(w, "{count:5} {steps:10} {traversals:10}")?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Runs every configuration of a given size (both halves, since the
+ * prediction does not rely on any symmetry) and returns the first one for
+ * which predict_majority returns a value which differs from the value the
+ * configuration really converges to. Configurations which do not converge
+ * within expected_convergence_bound updates, as decided by converge like
+ * for is_correct, also count as a mismatch if a prediction was made.
+ */
+#[cfg(feature = "search")]
+pub fn find_prediction_mismatch(size : u32) -> Option<u32> {
+    (0..1u32 << size)
+        .into_par_iter()
+        .find_any(|&k| {
+            let Some(prediction) = Configuration::new(k, size).predict_majority() else {
+                return false;
+            };
+
+            let mut x = Configuration::new(k, size);
+            ! x.converge() || prediction != (x.value & 1 != 0)
+        })
+}
+
+/**
+ * Runs every configuration of a given size for which is_trivially_correct
+ * holds, and returns the first one which check does not find correct,
+ * which would break the proof of the fast path.
+ */
+#[cfg(feature = "search")]
+pub fn find_fast_path_mismatch(size : u32) -> Option<u32> {
+    (0..1u32 << size)
+        .into_par_iter()
+        .find_any(|&k| {
+            let mut x = Configuration::new(k, size);
+            x.is_trivially_correct() && x.check() != Verdict::Correct
+        })
+}
+
+/**
+ * Flips each cell of an initial configuration in turn, and returns for each
+ * flipped index the value_hamming distance between the states the original
+ * and the flipped configurations are in after converge. For a correct rule,
+ * this is either 0 or the size, and it is the size only when the flip
+ * changes the majority.
+ */
+pub fn perturbation_study(x : &Configuration) -> Vec<(u32, u32)> {
+    let mut converged = *x;
+    converged.converge();
+
+    (0..x.size)
+        .map(|k| {
+            let mut flipped = *x;
+            flipped.value ^= 1 << k;
+            flipped.converge();
+            (k, converged.value_hamming(&flipped))
+        })
+        .collect()
+}
+
+/**
+ * Searches all the configurations of a given size, in parallel, for single
+ * cell flips which change the converged value in a way the densities don't
+ * explain, and returns them as (configuration, flipped index) pairs.
+ *
+ * Flipping one cell changes the difference between the numbers of 1s and
+ * 0s by 2, so it can only change the majority when that difference goes
+ * from 1 to -1 (odd sizes), or when one of the two configurations is a tie
+ * (even sizes). Pairs involving a tie are skipped, since ties may converge
+ * to anything. For every other pair, the converged values must change if
+ * and only if the majority does, and a pair where they don't, or where
+ * either configuration does not converge, is an anomaly. The expected
+ * changes are thus exactly the flips of odd size configurations whose
+ * 1s and 0s differ by one.
+ */
+#[cfg(feature = "search")]
+pub fn find_sensitivity_anomalies(size : u32) -> Vec<(u32, u32)> {
+    let majority = |value : u32| 2 * value.count_ones() > size;
+    let is_tie = |value : u32| 2 * value.count_ones() == size;
+
+    all_configurations(size)
+        .filter(|x| ! is_tie(x.value))
+        .flat_map_iter(|x| {
+            let mut converged = x;
+            let converged_ok = converged.converge();
+
+            (0..size)
+                .filter(move |&k| {
+                    let flipped = x.value ^ 1 << k;
+                    if is_tie(flipped) {
+                        return false;
+                    }
+
+                    let mut other = Configuration::new(flipped, size);
+                    if ! converged_ok || ! other.converge() {
+                        return true;
+                    }
+
+                    let changed = converged.value_hamming(&other) != 0;
+                    changed != (majority(x.value) != majority(flipped))
+                })
+                .map(move |k| (x.value, k))
+        })
+        .collect()
+}
+
+/**
+ * Measures how many configurations per second is_correct goes through at
+ * sizes 10 to 30, on one thread, and writes the curve. Each size runs the
+ * same number of random configurations, drawn beforehand from a fixed
+ * seed so that runs can be compared. The time is also divided by the
+ * number of cells updated (the size times the number of updates, counted
+ * in an untimed run) : the whole state of a configuration is seven u32
+ * numbers, so it always fits in the CPU cache, and this column should stay
+ * flat. A cliff at some size would point to a cache issue.
+ */
+#[cfg(feature = "search")]
+pub fn calibrate(w : &mut dyn Write) -> io::Result<()> {
+    const SAMPLES : usize = 200_000;
+    const SEED : u64 = 0x00C0_FFEE;
+
+    let mut rng = StdRng::seed_from_u64(SEED);
+    writeln!(w, "size  configurations/s  ns/configuration  ns/cell update")?;
+
+    for size in [10, 15, 20, 25, 30] {
+        let values : Vec<u32> = (0..SAMPLES).map(|_| rng.gen::<u32>() & ((1 << size) - 1)).collect();
+        let bound = expected_convergence_bound(size);
+        let cells : u64 = values.iter()
+            .map(|&value| (bound - Configuration::new(value, size).run_with_budget(bound).1) as u64 * size as u64)
+            .sum();
+
+        let start = std::time::Instant::now();
+        for &value in &values {
+            std::hint::black_box(Configuration::new(std::hint::black_box(value), size).is_correct());
+        }
+        let seconds = start.elapsed().as_secs_f64();
+
+        writeln!(w, "{size:4} {:17.0} {:17.1} {:15.2}",
+            SAMPLES as f64 / seconds, seconds * 1e9 / SAMPLES as f64, seconds * 1e9 / cells as f64)?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Times a full deterministic search of the given size in linear order, and
+ * then in Gray code order.
+ */
+#[cfg(feature = "search")]
+pub fn write_order_benchmark(size : u32, w : &mut dyn Write) -> io::Result<()> {
+    for gray_code in [false, true] {
+        let options = SearchOptions { deterministic : true, gray_code, ..SearchOptions::default() };
+        let start = std::time::Instant::now();
+        let result = find_counter_example(size, &options);
+        let order = if gray_code { "gray code" } else { "linear" };
+        writeln!(w, "{order} : {result:?} in {:?}", start.elapsed())?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Hashes the whole execution of a configuration, every state from the
+ * initial one to the converged one, into a single number. Two versions of
+ * the rule giving the same checksum on a configuration almost certainly
+ * go through the same states, not only to the same result.
+ *
+ * The hash is FNV-1a over the size and the layers of each state, as
+ * ordered by state_key, in little endian : unlike the hashers of the
+ * standard library, it does not depend on the platform nor on a random
+ * seed, so checksums can be compared between runs and machines. Like
+ * for_each_step, this never returns if the configuration does not converge.
+ */
+pub fn trace_checksum(size : u32, value : u32) -> u64 {
+    let mut hash = 0xCBF2_9CE4_8422_2325u64;
+
+    Configuration::new(value, size).for_each_step(|x| {
+        let layers = std::iter::once(x.size).chain(x.state_key(EqualityStrategy::FullState));
+        for byte in layers.flat_map(u32::to_le_bytes) {
+            hash = (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01B3);
+        }
+    });
+
+    hash
+}
+
+/**
+ * Folds the trace_checksum of every configuration of a given size, in
+ * increasing order of value, into a single checksum for the whole size.
+ */
+pub fn size_trace_checksum(size : u32) -> u64 {
+    (0..1 << size).fold(0, |hash, value| {
+        hash.rotate_left(5) ^ trace_checksum(size, value)
+    })
+}
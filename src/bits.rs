@@ -0,0 +1,62 @@
+/*
+ * The two bit manipulation helpers used by the local function, written
+ * for any width of unsigned number. They run on every width of word a
+ * Configuration is stored in, u32 as well as the u64 and u128 words of the
+ * larger sizes; the benchmarks also compare their cost across these widths.
+ */
+
+use std::ops::{BitAnd, BitOr, Not, Shl};
+
+/**
+ * The unsigned numbers on which the helpers can operate.
+ */
+pub trait Bits : Copy + PartialEq
+    + BitAnd<Output = Self> + BitOr<Output = Self>
+    + Not<Output = Self> + Shl<u32, Output = Self> {
+    // How many bits does the number hold?
+    const BITS : u32;
+    const ZERO : Self;
+    const ONE : Self;
+}
+
+macro_rules! impl_bits {
+    ($($t:ty),*) => {
+        $(
+            impl Bits for $t {
+                const BITS : u32 = <$t>::BITS;
+                const ZERO : Self = 0;
+                const ONE : Self = 1;
+            }
+        )*
+    }
+}
+
+impl_bits!(u32, u64, u128);
+
+/**
+ * A helper function which copies a flag from another in a number.
+ * Inlined for better performances.
+ */
+#[inline]
+pub fn self_assign<W : Bits>(mem : &mut W, to_index : u32, from_index : u32) {
+    if *mem & W::ONE << from_index != W::ZERO {
+        *mem = *mem | W::ONE << to_index;
+    }
+    else {
+        *mem = *mem & !(W::ONE << to_index);
+    }
+}
+
+/**
+ * A helper function which assigns a boolean value to a specific bit
+ * of a number. Inlined for better performances.
+ */
+#[inline]
+pub fn assign_bool<W : Bits>(to : &mut W, to_index : u32, value : bool) {
+    if value {
+        *to = *to | W::ONE << to_index;
+    }
+    else {
+        *to = *to & !(W::ONE << to_index);
+    }
+}
@@ -0,0 +1,147 @@
+/*
+ * The corpus of counter examples : a text file of configurations, one per
+ * line, which have been found to fail, and can be checked again after the
+ * rule changes.
+ */
+
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::{Configuration, Verdict};
+#[cfg(feature = "search")]
+use crate::check_batch;
+
+/**
+ * The result of re-verifying a corpus of counter-examples. Each entry
+ * is a (size, value) pair, in the order in which they appear in the corpus.
+ */
+#[derive(Debug, Default)]
+pub struct CorpusReport {
+    // Entries on which is_correct still returns false.
+    pub still_failing : Vec<(u32, u32)>,
+    // Entries on which is_correct now returns true.
+    pub now_passing : Vec<(u32, u32)>,
+}
+
+impl CorpusReport {
+    /**
+     * Returns true if every entry of the corpus still fails, meaning that
+     * the rule has not changed in a way visible from the corpus.
+     */
+    pub fn is_reproduced(&self) -> bool {
+        self.now_passing.is_empty()
+    }
+}
+
+/**
+ * Returns a configuration in the format read by Configuration::from_str :
+ * its values as 0s and 1s, starting with the cell of index 0.
+ */
+pub(crate) fn corpus_line(size : u32, value : u32) -> String {
+    Configuration::new(value, size).value_string()
+}
+
+/**
+ * Appends a counter example to a corpus file, creating the file if needed,
+ * unless it is already in it. The line is flushed right away, so that
+ * counter examples are not lost if the search is interrupted. Returns true
+ * if the counter example was added.
+ */
+pub fn append_to_corpus(path : impl AsRef<Path>, size : u32, value : u32) -> io::Result<bool> {
+    let path = path.as_ref();
+    if path.exists() && read_corpus(path)?.contains(&(size, value)) {
+        return Ok(false);
+    }
+
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", corpus_line(size, value))?;
+    file.flush()?;
+
+    Ok(true)
+}
+
+/**
+ * Reads a corpus of counter-examples from a file. The file contains one
+ * configuration per line, in the format accepted by Configuration::from_str.
+ * Empty lines and lines starting with # are ignored.
+ */
+pub fn read_corpus(path : impl AsRef<Path>) -> io::Result<Vec<(u32, u32)>> {
+    let mut entries = vec![];
+
+    for (number, line) in fs::read_to_string(path)?.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let x = Configuration::from_str(line).map_err(|e| io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("line {} : {e}", number + 1),
+        ))?;
+        entries.push((x.size, x.value));
+    }
+
+    Ok(entries)
+}
+
+/**
+ * Loads a corpus of previously found counter-examples and runs each of them
+ * again, sorting them between those which still fail and those which now
+ * pass. If the rule was not modified, all of them should still fail.
+ */
+#[cfg(feature = "search")]
+pub fn reverify_corpus(path : impl AsRef<Path>) -> io::Result<CorpusReport> {
+    let mut report = CorpusReport::default();
+    let entries = read_corpus(path)?;
+
+    for (&entry, verdict) in entries.iter().zip(check_batch(&entries)) {
+        match verdict {
+            Verdict::Correct => report.now_passing.push(entry),
+            Verdict::WrongValue | Verdict::DidNotConverge => report.still_failing.push(entry),
+        }
+    }
+
+    Ok(report)
+}
+
+/**
+ * Reads configurations, one per line in the format accepted by
+ * Configuration::from_str, until the end of the input, and writes the
+ * verdict of each of them on its own line. As in a corpus, empty lines and
+ * lines starting with # are ignored. Malformed lines, including ones which
+ * are not valid UTF-8, are reported and skipped. Returns true if all the
+ * lines were configurations which converge to their majority value.
+ */
+pub fn check_lines(r : &mut dyn BufRead, w : &mut dyn Write) -> io::Result<bool> {
+    let mut all_correct = true;
+
+    for (number, line) in r.split(b'\n').enumerate() {
+        let line = String::from_utf8_lossy(&line?).into_owned();
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let verdict = match Configuration::from_str(line) {
+            Ok(mut x) => x.check(),
+            Err(e) => {
+                writeln!(w, "line {} : {e}", number + 1)?;
+                all_correct = false;
+                continue;
+            },
+        };
+
+        let label = match verdict {
+            Verdict::Correct => "correct",
+            Verdict::WrongValue => "wrong value",
+            Verdict::DidNotConverge => "did not converge",
+        };
+        writeln!(w, "{line} : {label}")?;
+        all_correct &= verdict == Verdict::Correct;
+    }
+
+    Ok(all_correct)
+}
+
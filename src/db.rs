@@ -0,0 +1,163 @@
+/*
+ * The results database : the outcomes of the searches, their counter
+ * examples and the statistics of their executions, stored in SQLite
+ * tables so that the runs, and the versions of the rule they ran, can be
+ * queried and compared later. The database is written through the sqlite3
+ * command line shell, which has to be installed and on the PATH, rather
+ * than through a binding to the SQLite library : the build stays free of C
+ * code, and a database is only written a few times per size. Opening a
+ * database first checks that the shell runs, so a missing one is reported
+ * before any search starts.
+ */
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::SystemTime;
+
+use crate::{rule_fingerprint, search_order, unix_seconds, Configuration64, Rule, SearchOptions, Sequential, SizeReport};
+
+// created on the first use of a database, and left alone afterwards
+const SCHEMA : &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    started REAL NOT NULL,
+    version TEXT NOT NULL,
+    rule TEXT NOT NULL,
+    fingerprint TEXT NOT NULL,
+    search_order TEXT NOT NULL,
+    shard TEXT,
+    command TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS sizes (
+    run INTEGER NOT NULL REFERENCES runs (id),
+    size INTEGER NOT NULL,
+    started REAL NOT NULL,
+    finished REAL NOT NULL,
+    from_index INTEGER NOT NULL,
+    to_index INTEGER NOT NULL,
+    tested INTEGER NOT NULL,
+    total INTEGER NOT NULL,
+    skipped INTEGER NOT NULL,
+    seconds REAL NOT NULL,
+    max_iterations INTEGER,
+    mean_iterations REAL,
+    result TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS counter_examples (
+    run INTEGER NOT NULL REFERENCES runs (id),
+    size INTEGER NOT NULL,
+    value INTEGER NOT NULL,
+    hex TEXT NOT NULL,
+    verdict TEXT NOT NULL
+);
+";
+
+/**
+ * A results database, and the run of the program it records : each run is
+ * a row of the runs table, with the version of the program, the name of
+ * the rule the searches run (see Rule::NAME) and the fingerprint of its
+ * local function (see rule_fingerprint), the order of the
+ * searches and the command line, and the sizes it searched and the
+ * counter examples it found point to it.
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResultsDb {
+    pub path : PathBuf,
+    // the id of the row of the run in the runs table
+    pub run : i64,
+}
+
+impl ResultsDb {
+    /**
+     * Opens the database at the given path, creating it and its tables if
+     * needed, and records a new run with the given options. Fails right
+     * away if the sqlite3 shell cannot be run.
+     */
+    pub fn open(path : impl AsRef<Path>, options : &SearchOptions) -> io::Result<Self> {
+        check_sqlite3()?;
+        let path = path.as_ref().to_path_buf();
+        let command : Vec<String> = std::env::args().collect();
+        let shard = options.shard.as_ref().map(|shard| format!("{}/{}", shard.index, shard.count));
+
+        let output = execute(&path, &format!(
+            "{SCHEMA}INSERT INTO runs (started, version, rule, fingerprint, search_order, shard, command) \
+                VALUES ({:.3}, {}, {}, '{:016X}', {}, {}, {});\nSELECT last_insert_rowid();\n",
+            unix_seconds(SystemTime::now()), quote(env!("CARGO_PKG_VERSION")), quote(Sequential::NAME), rule_fingerprint(),
+            quote(&search_order(options)), shard.as_deref().map_or("NULL".to_string(), quote), quote(&command.join(" ")),
+        ))?;
+
+        let run = output.trim().parse().map_err(|_| io::Error::new(io::ErrorKind::InvalidData,
+            format!("unexpected answer of sqlite3 : {output:?}")))?;
+        Ok(ResultsDb { path, run })
+    }
+
+    /**
+     * Records the search of a size, which started at the given time and
+     * searched the given slice of indices, and its counter examples, in a
+     * single transaction.
+     */
+    pub fn record(&self, started : SystemTime, indices : std::ops::Range<u64>, report : &SizeReport) -> io::Result<()> {
+        let iterations = report.iterations.filter(|iterations| iterations.runs > 0);
+        let mut sql = format!(
+            "BEGIN;\nINSERT INTO sizes VALUES ({}, {}, {:.3}, {:.3}, {}, {}, {}, {}, {}, {:.3}, {}, {}, {});\n",
+            self.run, report.size, unix_seconds(started), unix_seconds(SystemTime::now()), indices.start, indices.end,
+            report.tested, report.total, report.skipped, report.seconds,
+            iterations.map_or("NULL".to_string(), |iterations| iterations.max.to_string()),
+            iterations.and_then(|iterations| iterations.mean()).map_or("NULL".to_string(), |mean| format!("{mean:.3}")),
+            quote(report.result),
+        );
+        for &value in &report.counter_examples {
+            let verdict = Configuration64::new(value, report.size).check();
+            sql += &format!("INSERT INTO counter_examples VALUES ({}, {}, {value}, '{value:#X}', '{verdict:?}');\n",
+                self.run, report.size);
+        }
+        sql += "COMMIT;\n";
+
+        execute(&self.path, &sql).map(|_| ())
+    }
+}
+
+/**
+ * Quotes a string as an SQL literal.
+ */
+fn quote(text : &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+/**
+ * Checks that the sqlite3 shell is installed, by asking its version.
+ */
+fn check_sqlite3() -> io::Result<()> {
+    let output = Command::new("sqlite3").arg("-version").output().map_err(|e| io::Error::new(e.kind(),
+        format!("the results database needs the sqlite3 command line shell, which could not be run : {e}")))?;
+
+    if ! output.status.success() {
+        return Err(io::Error::other(format!("sqlite3 -version failed : {}",
+            String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    Ok(())
+}
+
+/**
+ * Runs SQL statements on the database at the given path with the sqlite3
+ * shell, stopping at the first error, and returns what they printed.
+ */
+fn execute(path : &Path, sql : &str) -> io::Result<String> {
+    let mut child = Command::new("sqlite3")
+        .arg("-bail")
+        .arg(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| io::Error::new(e.kind(), format!("could not run sqlite3, is it installed? {e}")))?;
+
+    child.stdin.take().expect("the input of sqlite3 is piped").write_all(sql.as_bytes())?;
+    let output = child.wait_with_output()?;
+
+    if ! output.status.success() {
+        return Err(io::Error::other(format!("sqlite3 failed : {}", String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
@@ -0,0 +1,119 @@
+/*
+ * A configuration whose size is a compile time constant. It is the same
+ * Configuration underneath, but with the size known to the compiler, the
+ * sweep of update is unrolled and the left index of every cell, including
+ * the wrap around of cell 0, is folded into a constant. The exhaustive
+ * searches run on it, through fixed_check which picks the right size at
+ * run time.
+ */
+
+use crate::{expected_convergence_bound, Configuration, TiePolicy, Verdict};
+
+/**
+ * A configuration of N cells, N being at most Configuration::MAX_SIZE.
+ * It behaves exactly like the Configuration of the same size and value.
+ */
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct FixedConfiguration<const N : u32> {
+    configuration : Configuration,
+}
+
+impl<const N : u32> FixedConfiguration<N> {
+    // evaluated, and thus checked, whenever a size is used
+    const VALID_SIZE : () = assert!(N >= 1 && N <= Configuration::<u32>::MAX_SIZE, "unsupported size");
+
+    // the mask of the N cells
+    const MASK : u32 = (1 << N) - 1;
+
+    /**
+     * Creates the configuration of size N with the given value.
+     */
+    pub fn new(value : u32) -> Self {
+        let () = Self::VALID_SIZE;
+        Self { configuration : Configuration::new(value, N) }
+    }
+
+    /**
+     * Returns the same configuration, with its size known at run time only.
+     */
+    pub fn configuration(&self) -> Configuration {
+        self.configuration
+    }
+
+    /**
+     * Same as Configuration::update.
+     */
+    #[inline]
+    pub fn update(&mut self) {
+        self.configuration.apply_local_function(N - 1, 0);
+
+        for k in 1..N {
+            self.configuration.apply_local_function(k - 1, k);
+        }
+    }
+
+    /**
+     * Same as Configuration::has_converged.
+     */
+    #[inline]
+    pub fn has_converged(&self) -> bool {
+        self.configuration.alphabet == 0 &&
+        (self.configuration.value == 0 || self.configuration.value == Self::MASK)
+    }
+
+    /**
+     * Same as Configuration::check.
+     */
+    pub fn check(&mut self) -> Verdict {
+        let Some(majority) = self.configuration.majority(TiePolicy::Undefined) else {
+            return Verdict::Correct;
+        };
+
+        let mut iteration_count = 0;
+
+        while ! self.has_converged() {
+            if iteration_count >= expected_convergence_bound(N) {
+                return Verdict::DidNotConverge;
+            }
+
+            self.update();
+            iteration_count += 1;
+        }
+
+        if majority as u32 == self.configuration.value & 1 { Verdict::Correct }
+        else { Verdict::WrongValue }
+    }
+
+    /**
+     * Same as Configuration::is_correct.
+     */
+    pub fn is_correct(&mut self) -> bool {
+        self.check() == Verdict::Correct
+    }
+}
+
+/**
+ * Checks the configuration of size N with the given value.
+ */
+fn check_value<const N : u32>(value : u32) -> Verdict {
+    FixedConfiguration::<N>::new(value).check()
+}
+
+macro_rules! fixed_checks {
+    ($size:expr, $($n:literal)*) => {
+        match $size {
+            $($n => check_value::<$n> as fn(u32) -> Verdict,)*
+            size => panic!("no fixed configuration of size {size}"),
+        }
+    }
+}
+
+/**
+ * Returns the function checking the configurations of the given size, from
+ * 1 to Configuration::MAX_SIZE, with the FixedConfiguration of that size :
+ * fixed_check(size)(value) is Configuration::new(value, size).check(),
+ * only faster.
+ */
+pub fn fixed_check(size : u32) -> fn(u32) -> Verdict {
+    fixed_checks!(size, 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31)
+}
@@ -0,0 +1,43 @@
+/*
+ * Catching SIGINT and SIGTERM during the searches, so they can stop
+ * cleanly and save their progress instead of losing hours of work. This
+ * only exists on unix; elsewhere the signals keep their default behavior.
+ */
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static INTERRUPTED : AtomicBool = AtomicBool::new(false);
+
+/**
+ * Returns true once SIGINT or SIGTERM was received, if
+ * install_interrupt_handler was called.
+ */
+#[inline]
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/**
+ * Makes SIGINT and SIGTERM set the flag of interrupted instead of killing
+ * the process : the searches poll it, and stop as if their deadline had
+ * passed. A second signal kills the process right away, for when nothing
+ * polls the flag.
+ */
+#[cfg(unix)]
+pub fn install_interrupt_handler() {
+    extern "C" fn handle(_ : libc::c_int) {
+        // only async-signal-safe calls here
+        if INTERRUPTED.swap(true, Ordering::Relaxed) {
+            unsafe { libc::_exit(130) };
+        }
+    }
+
+    let handler = handle as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn install_interrupt_handler() {}
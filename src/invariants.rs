@@ -0,0 +1,261 @@
+/*
+ * The invariants of the rule, checked by the invariants command on all the
+ * small sizes : the uniform configurations are fixed points, the rotations,
+ * mirror images and complements run alike, the uniform regions are
+ * preserved, each case of the local function behaves as expected, and the
+ * executions at the edges of the sizes do not overflow.
+ */
+
+use std::io::{self, Write};
+
+use crate::{
+    canonical_form, check_complement_symmetry, check_reflection_symmetry, check_rotation_symmetry,
+    describe_cell, local_branch, local_state, local_step, orbit_size, CellState, Configuration,
+    RuleBranch,
+};
+
+/**
+ * Checks that the all-0 and all-1 configurations of a given size are fixed
+ * points of the rule, and returns the value of the one which is not, if any.
+ * Every convergence check relies on this.
+ */
+pub(crate) fn check_uniform_fixed_points(size : u32) -> Option<u32> {
+    [0, (1 << size) - 1].into_iter()
+        .find(|&value| ! Configuration::new(value, size).is_fixed_point())
+}
+
+/**
+ * Runs one update of a configuration cell by cell, and checks the local
+ * property behind uniform regions : a boolean cell whose left neighbor is
+ * boolean with the same value, at the time the cell is updated, is left
+ * unchanged in all its layers. Returns the index of the first cell which
+ * breaks it, if any. The configuration is left updated.
+ *
+ * The property is about the time of the update, and does not make whole
+ * uniform blocks stable : the sweep goes from left to right, so a head
+ * coming from the left enters a block in the same sweep, and every cell it
+ * scans becomes intermediate. A block is only preserved when no head
+ * reaches it during the sweep, see check_first_sweep.
+ */
+pub(crate) fn check_uniform_regions(x : &mut Configuration) -> Option<u32> {
+    let mut violation = None;
+
+    for k in 0..x.size {
+        let left = x.left_of(k);
+        let before = *x;
+        let uniform = (x.alphabet & (1 << left | 1 << k)) == 0 &&
+            (x.value >> left & 1) == (x.value >> k & 1);
+
+        x.apply_local_function(left, k);
+        if uniform && *x != before {
+            violation.get_or_insert(k);
+        }
+    }
+
+    violation
+}
+
+/**
+ * Checks uniform regions on all the configurations of a given size : the
+ * local property of check_uniform_regions at every update of their
+ * executions, and its consequence on the first update, from a state where
+ * every cell is boolean. There, the cells before the first one whose value
+ * differs from its left neighbor (in the order of the sweep) are in uniform
+ * regions and are left unchanged, and every cell from that one on becomes
+ * intermediate, the kickstarted head scanning the rest of the ring.
+ * Returns the value of the first configuration for which it does not hold,
+ * if any.
+ */
+pub fn check_first_sweep(size : u32) -> Option<u32> {
+    (0..1 << size).find(|&value| {
+        let initial = Configuration::new(value, size);
+
+        let mut x = initial;
+        let mut broken = false;
+        while ! x.has_converged() && ! broken {
+            broken = check_uniform_regions(&mut x).is_some();
+        }
+
+        let mut x = initial;
+        x.update();
+        let first = (0..size).find(|&k| (value >> x.left_of(k) & 1) != (value >> k & 1));
+        let preserved = first.unwrap_or(size);
+        let unchanged = (0..preserved).all(|k| describe_cell(&x, k) == describe_cell(&initial, k));
+        let scanned = (preserved..size).all(|k| x.alphabet & 1 << k != 0);
+
+        broken || ! unchanged || ! scanned
+    })
+}
+
+/**
+ * Returns true if running the given function panics, without letting the
+ * panic message through.
+ */
+fn panics(f : impl FnOnce() + std::panic::UnwindSafe) -> bool {
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    let panicked = std::panic::catch_unwind(f).is_err();
+    std::panic::set_hook(hook);
+
+    panicked
+}
+
+/**
+ * Returns true if the program was built with overflow checks, as with
+ * the verify profile, so that integer overflows panic instead of wrapping.
+ */
+fn overflow_checks_enabled() -> bool {
+    panics(|| { let _ = std::hint::black_box(u32::MAX) + 1; })
+}
+
+/**
+ * Checks the sizes at the edges of what a configuration can hold, and
+ * returns a description of the first problem found, if any. Running whole
+ * executions at MAX_SIZE must never overflow, which only panics when
+ * overflow checks are enabled. With them, an update at size 0 must also
+ * trip on the underflow of size - 1 rather than wrap around.
+ */
+fn check_edge_sizes() -> Option<&'static str> {
+    let size = Configuration::<u32>::MAX_SIZE;
+    let mask = (1 << size) - 1;
+    let values = [0, mask, 1, mask >> 1, mask ^ 1, Configuration::alternating(size, true).value,
+        (1 << (size / 2)) - 1];
+
+    let overflowed = panics(|| for value in values {
+        let mut x = Configuration::new(value, size);
+        let _ = (x.density(), x.to_token(), canonical_form(value, size), orbit_size(value, size));
+        let _ = Configuration::new(value, size).summarize();
+        let _ = (x.update_with_changes(), x.intermediate_indices(), x.progress_fraction());
+        let _ = Configuration::new(value, size).check();
+    });
+    if overflowed {
+        return Some("an execution of size MAX_SIZE overflows");
+    }
+
+    if overflow_checks_enabled() && ! panics(|| Configuration::new(0u32, 0).update()) {
+        return Some("an update of size 0 does not trip on its underflow");
+    }
+
+    None
+}
+
+/**
+ * Builds the state of a cell from its description, as written by
+ * CellState::describe. Boolean cells are given no color nor memory.
+ */
+pub(crate) fn cell_from_description(description : &str) -> CellState {
+    let chars : Vec<char> = description.chars().collect();
+
+    CellState {
+        intermediate : chars[0] == 'i',
+        value : chars[1] == '1',
+        taken : chars[2] == 'X',
+        color : chars.get(3) == Some(&'R'),
+        mem_0 : matches!(chars.get(4), Some('.' | ';')),
+        mem_1 : matches!(chars.get(4), Some(',' | ';')),
+    }
+}
+
+/**
+ * Drives each case of local_step from a crafted cell and left neighbor,
+ * and checks that local_branch names the expected case and that the cell
+ * ends in the expected state. Then checks that apply_local_function and
+ * rule_branch agree with them on every pair of states, packed as the two
+ * cells of a configuration. Returns the first case which does not behave
+ * as expected, if any.
+ */
+fn check_rule_branches() -> Option<RuleBranch> {
+    let cases = [
+        // 1 1 stays 1 1
+        (RuleBranch::Stable, "b1-", "b1-", "b1-"),
+        // 0 1 : the 1 becomes intermediate, remembers and takes its 1
+        (RuleBranch::Kickstart, "b0-", "b1-", "i1XB,"),
+        // boolean 1 then intermediate : the cell becomes a boolean 1
+        (RuleBranch::Propagation, "b1-", "i0XB_", "b1X"),
+        // intermediate R remembering a 0, then a boolean 1 : the 1 is taken
+        (RuleBranch::Scanning, "i0XR.", "b1-", "i1XR;"),
+        // intermediate R remembering a 0, then a B which took its 0 : the
+        // cell only copies the color and memory
+        (RuleBranch::Scanning, "i0XR.", "i0XB,", "i0XR."),
+        // two B with a complete memory on the left : the cell flips to R
+        (RuleBranch::ColorFlip, "i0XB;", "i0XB;", "i0XR_"),
+        // two B, only 1s on the left : the cell reverts to a boolean 1
+        (RuleBranch::RevertToOne, "i0XB,", "i0XB_", "b1X"),
+        // two B, only 0s on the left : the cell reverts to a boolean 0
+        (RuleBranch::RevertToZero, "i0XB.", "i1XB_", "b0X"),
+    ];
+
+    let failed = cases.into_iter().find_map(|(branch, left, current, after)| {
+        let (left, current) = (cell_from_description(left), cell_from_description(current));
+        let fired = local_branch(left, current);
+        (fired != branch || local_step(left, current) != cell_from_description(after)).then_some(branch)
+    });
+    if failed.is_some() {
+        return failed;
+    }
+
+    (0..64 * 64).find_map(|bits| {
+        let (left, current) = (local_state(bits & 63), local_state(bits >> 6));
+        let mut x = Configuration {
+            size : 2,
+            alphabet : bits & 1 | bits >> 5 & 2,
+            value : bits >> 1 & 1 | bits >> 6 & 2,
+            taken : bits >> 2 & 1 | bits >> 7 & 2,
+            color : bits >> 3 & 1 | bits >> 8 & 2,
+            mem_0 : bits >> 4 & 1 | bits >> 9 & 2,
+            mem_1 : bits >> 5 & 1 | bits >> 10 & 2,
+        };
+
+        let branch = local_branch(left, current);
+        let fired = x.rule_branch(0, 1);
+        x.apply_local_function(0, 1);
+        let agrees = x.cell(0) == left && x.cell(1) == local_step(left, current);
+        (fired != branch || ! agrees).then_some(branch)
+    })
+}
+
+/**
+ * Checks the invariants of the rule on all small sizes, and writes the
+ * first violation found. Returns true if they all hold. The agreement of
+ * the other parts of the program with the rule, such as the wider
+ * configurations or the orbits, is checked by the tests.
+ */
+pub fn check_invariants(w : &mut dyn Write) -> io::Result<bool> {
+    if ! overflow_checks_enabled() {
+        writeln!(w, "overflow checks are disabled, run with --profile verify to enable them")?;
+    }
+    if let Some(problem) = check_edge_sizes() {
+        writeln!(w, "{problem}")?;
+        return Ok(false);
+    }
+    if let Some(branch) = check_rule_branches() {
+        writeln!(w, "the {branch:?} case of the local function does not behave as expected")?;
+        return Ok(false);
+    }
+
+    for size in 2..=12 {
+        if let Some(value) = check_uniform_fixed_points(size) {
+            writeln!(w, "size {size} : uniform configuration {value:#X} is not a fixed point")?;
+            return Ok(false);
+        }
+        if let Some(value) = check_rotation_symmetry(size) {
+            writeln!(w, "size {size} : the rotations of {value:#X} do not agree")?;
+            return Ok(false);
+        }
+        if let Some(value) = check_complement_symmetry(size) {
+            writeln!(w, "size {size} : the complement of {value:#X} runs differently")?;
+            return Ok(false);
+        }
+        if let Some(value) = check_reflection_symmetry(size) {
+            writeln!(w, "size {size} : the mirror image of {value:#X} runs differently")?;
+            return Ok(false);
+        }
+        if let Some(value) = check_first_sweep(size) {
+            writeln!(w, "size {size} : the uniform regions of {value:#X} are not preserved")?;
+            return Ok(false);
+        }
+    }
+
+    writeln!(w, "all invariants hold")?;
+    Ok(true)
+}
@@ -0,0 +1,262 @@
+/*
+ * A configuration of any size, whose layers are vectors of u64 words
+ * instead of single numbers. It is much slower than the fixed width
+ * configurations, since every cell goes through local_step, but it has no
+ * size limit : it is meant for random executions and sampled checks on
+ * rings of thousands of cells.
+ */
+
+use std::io::{self, Write};
+
+#[cfg(feature = "search")]
+use rand::Rng;
+
+use crate::{expected_convergence_bound, local_step, write_ruler, CellState, Configuration, PrintOptions, Verdict, Word};
+
+/**
+ * A configuration of any size, with the same layers as Configuration.
+ * Cell k is bit k % 64 of word k / 64 of each layer, and the bits beyond
+ * the size are always 0.
+ */
+#[derive(Default, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct LargeConfiguration {
+    pub size : u32,
+    pub value : Vec<u64>,
+    pub alphabet : Vec<u64>,
+    pub taken : Vec<u64>,
+    pub color : Vec<u64>,
+    pub mem_0 : Vec<u64>,
+    pub mem_1 : Vec<u64>,
+}
+
+/**
+ * Returns bit k of a layer.
+ */
+fn get(layer : &[u64], k : u32) -> bool {
+    layer[k as usize / 64] & 1 << (k % 64) != 0
+}
+
+/**
+ * Sets bit k of a layer to the given value.
+ */
+fn set(layer : &mut [u64], k : u32, bit : bool) {
+    let word = &mut layer[k as usize / 64];
+    *word = *word & ! (1 << (k % 64)) | (bit as u64) << (k % 64);
+}
+
+impl LargeConfiguration {
+    /**
+     * Creates the configuration of a given size whose values are all 0.
+     */
+    pub fn new(size : u32) -> Self {
+        let words = vec![0; size.div_ceil(64) as usize];
+
+        Self {
+            size,
+            value : words.clone(),
+            alphabet : words.clone(),
+            taken : words.clone(),
+            color : words.clone(),
+            mem_0 : words.clone(),
+            mem_1 : words,
+        }
+    }
+
+    /**
+     * Creates the configuration whose cell k has the value values[k].
+     */
+    pub fn from_values(values : &[bool]) -> Self {
+        let mut x = Self::new(values.len() as u32);
+        for (k, &bit) in values.iter().enumerate() {
+            set(&mut x.value, k as u32, bit);
+        }
+        x
+    }
+
+    /**
+     * Creates a configuration of a given size whose values are drawn from
+     * the given generator.
+     */
+    #[cfg(feature = "search")]
+    pub fn random(size : u32, rng : &mut impl Rng) -> Self {
+        let mut x = Self::new(size);
+        for word in x.value.iter_mut() {
+            *word = rng.gen();
+        }
+        if ! size.is_multiple_of(64) {
+            if let Some(last) = x.value.last_mut() {
+                *last &= (1 << (size % 64)) - 1;
+            }
+        }
+        x
+    }
+
+    /**
+     * Returns the state of the cell at a given index.
+     */
+    pub fn cell(&self, index : u32) -> CellState {
+        CellState {
+            intermediate : get(&self.alphabet, index),
+            value : get(&self.value, index),
+            taken : get(&self.taken, index),
+            color : get(&self.color, index),
+            mem_0 : get(&self.mem_0, index),
+            mem_1 : get(&self.mem_1, index),
+        }
+    }
+
+    /**
+     * Sets the state of the cell at a given index.
+     */
+    pub fn set_cell(&mut self, index : u32, cell : CellState) {
+        set(&mut self.alphabet, index, cell.intermediate);
+        set(&mut self.value, index, cell.value);
+        set(&mut self.taken, index, cell.taken);
+        set(&mut self.color, index, cell.color);
+        set(&mut self.mem_0, index, cell.mem_0);
+        set(&mut self.mem_1, index, cell.mem_1);
+    }
+
+    /**
+     * Applies the local function at a given index, through local_step.
+     */
+    pub fn apply_local_function(&mut self, left : u32, index : u32) {
+        let cell = local_step(self.cell(left), self.cell(index));
+        self.set_cell(index, cell);
+    }
+
+    /**
+     * Applies the local function on every index in order.
+     */
+    pub fn update(&mut self) {
+        self.apply_local_function(self.size - 1, 0);
+
+        for k in 1..self.size {
+            self.apply_local_function(k - 1, k);
+        }
+    }
+
+    /**
+     * Returns the number of cells whose value is 1.
+     */
+    pub fn count_ones(&self) -> u32 {
+        self.value.iter().map(|word| word.count_ones()).sum()
+    }
+
+    /**
+     * Returns true if the configuration contains no intermediary symbol
+     * and that all the values are either 0 or 1.
+     */
+    pub fn has_converged(&self) -> bool {
+        let ones = self.count_ones();
+        self.alphabet.iter().all(|&word| word == 0) && (ones == 0 || ones == self.size)
+    }
+
+    /**
+     * Returns the value of the majority of the cells, or None on a tie.
+     */
+    pub fn majority(&self) -> Option<bool> {
+        let count_1 = self.count_ones();
+        let count_0 = self.size - count_1;
+
+        (count_0 != count_1).then_some(count_1 > count_0)
+    }
+
+    /**
+     * Same as Configuration::check : runs the configuration for at most
+     * expected_convergence_bound updates, and compares the value it
+     * converged to with its majority. Ties are always correct.
+     */
+    pub fn check(&mut self) -> Verdict {
+        let Some(majority) = self.majority() else {
+            return Verdict::Correct;
+        };
+
+        let mut iteration_count = 0;
+
+        while ! self.has_converged() {
+            if iteration_count >= expected_convergence_bound(self.size) {
+                return Verdict::DidNotConverge;
+            }
+
+            self.update();
+            iteration_count += 1;
+        }
+
+        if majority == get(&self.value, 0) { Verdict::Correct }
+        else { Verdict::WrongValue }
+    }
+
+    /**
+     * Returns true if the rule computes the right density value for this
+     * configuration, see check.
+     */
+    pub fn is_correct(&mut self) -> bool {
+        self.check() == Verdict::Correct
+    }
+
+    /**
+     * Runs the configuration until it converges, calling f on the initial
+     * state and then after each update, and stops at the first error of f.
+     */
+    pub fn try_for_each_step<E>(&mut self, mut f : impl FnMut(&LargeConfiguration) -> Result<(), E>)
+        -> Result<(), E> {
+        f(self)?;
+        while ! self.has_converged() {
+            self.update();
+            f(self)?;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Returns the first line printed by the println method, see
+     * Configuration::value_string.
+     */
+    pub fn value_string(&self) -> String {
+        (0..self.size).map(|k| self.cell(k).glyphs()[0]).collect()
+    }
+
+    /**
+     * Prints the configuration with the same three lines as
+     * Configuration::println.
+     */
+    pub fn println(&self) {
+        self.write(&mut io::stdout()).expect("could not print the configuration");
+    }
+
+    /**
+     * Writes the configuration with the same three lines as the println
+     * method.
+     */
+    pub fn write(&self, w : &mut dyn Write) -> io::Result<()> {
+        for line in 0..3 {
+            let glyphs : String = (0..self.size).map(|k| self.cell(k).glyphs()[line]).collect();
+            writeln!(w, "{glyphs}")?;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Same as write_execution, for this configuration. Time always goes
+     * from top to bottom.
+     */
+    pub fn write_execution(&mut self, options : &PrintOptions, w : &mut dyn Write) -> io::Result<()> {
+        if options.ruler {
+            write_ruler(self.size, w)?;
+        }
+        self.try_for_each_step(|x| x.write(w))
+    }
+}
+
+impl<W : Word> From<Configuration<W>> for LargeConfiguration {
+    fn from(x : Configuration<W>) -> Self {
+        let mut large = Self::new(x.size);
+        for k in 0..x.size {
+            large.set_cell(k, x.cell(k));
+        }
+        large
+    }
+}
@@ -0,0 +1,2734 @@
+/*
+ * written by Pacôme Perrotin
+ */
+
+/*
+ * This library checks the validity of our sequential solution to the
+ * density classification task on all configurations up to size 30. It
+ * holds the Configuration type and its executions; the local rule, the
+ * searches over all the configurations of a size, the checks of the rule
+ * and the renderings of the executions are in their own modules, and
+ * re-exported here. The binary is only the command line around them.
+ */
+
+#[cfg(feature = "search")]
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+use std::str::FromStr;
+
+mod analysis;
+mod bits;
+mod corpus;
+mod db;
+mod fixed;
+#[cfg(feature = "search")]
+mod interrupt;
+mod invariants;
+mod large;
+mod map;
+mod png;
+mod render;
+mod report;
+mod rule;
+mod sampling;
+mod search;
+mod shard;
+mod stats;
+mod symmetry;
+mod wide;
+
+pub use analysis::{perturbation_study, size_trace_checksum, trace_checksum, DensityError};
+#[cfg(feature = "search")]
+pub use analysis::{
+    calibrate, check_ties, failure_histogram_by_density, find_counter_example_at_density,
+    find_fast_path_mismatch, find_prediction_mismatch, find_sensitivity_anomalies,
+    write_failure_histogram, write_order_benchmark, write_traversal_histogram,
+};
+use bits::{assign_bool, self_assign};
+pub use bits::Bits;
+pub use corpus::{append_to_corpus, check_lines, read_corpus, CorpusReport};
+#[cfg(feature = "search")]
+pub use corpus::reverify_corpus;
+pub use db::ResultsDb;
+pub use fixed::{fixed_check, FixedConfiguration};
+#[cfg(feature = "search")]
+pub use interrupt::{install_interrupt_handler, interrupted};
+pub use invariants::{check_first_sweep, check_invariants};
+pub use large::LargeConfiguration;
+pub use map::{read_convergence_map, write_map_diff, ConvergenceMap, MapChange};
+#[cfg(feature = "search")]
+pub use map::{convergence_map, write_convergence_map};
+use render::{describe_cell, write_ruler};
+pub use render::{
+    dot_export, render_multi, show_execution, write_execution, write_execution_png, write_ndjson,
+    write_summary, write_taken_history, write_traced_execution, Backend, PrintOptions,
+};
+#[cfg(feature = "search")]
+pub use render::show_random_execution;
+use report::unix_seconds;
+pub use report::{append_stats_row, SizeReport, STATS_CSV_HEADER};
+#[cfg(feature = "search")]
+pub use report::append_results_log;
+use rule::{apply_rule, local_state, Cells, Layer};
+pub use rule::{
+    local_branch, local_step, rule_fingerprint, write_transition_table, CellState, RuleBranch,
+};
+pub use sampling::{SampleOptions, SampleOutcome, StratifiedOutcome};
+#[cfg(feature = "search")]
+pub use sampling::{fuzz, fuzz_large, sample, sample_stratified, write_fuzz, write_sample};
+#[cfg(feature = "search")]
+use search::search_range;
+pub use search::{
+    search_order, Checkpoint, IterationStats, OutputFormat, ProgressCallback, Reduction,
+    SearchOptions, SearchOutcome, SearchProgress, SearchStatus, PROGRESS_INTERVAL,
+};
+#[cfg(feature = "search")]
+pub use search::{
+    all_configurations, check_batch, find_counter_example, first_failing_size, is_reduced_search,
+    progress_bar, search_all, search_configurations, search_configurations_in, search_length,
+    search_size, search_size_from, size_is_clean, write_counter_example,
+};
+#[cfg(feature = "search")]
+use shard::write_shard_result;
+pub use shard::{read_shard_results, write_merge, MergeVerdict, Shard, ShardResult};
+pub use stats::Interval;
+use symmetry::{
+    bit_indices, check_complement_symmetry, check_reflection_symmetry, check_rotation_symmetry,
+};
+pub use symmetry::{
+    bracelet_count, canonical_form, check_orbit, complement, complement_asymmetries,
+    complement_compatible, dihedral_form, necklace_count, orbit_size, reflect,
+    reflection_compatible, rotate, rotation_compatible, same_orbit, write_complement_symmetry,
+    write_orbit, Necklaces, OrbitReport, REFLECTION_CHECK_SIZE,
+};
+pub use wide::{Configuration128, Configuration64, Word};
+
+#[cfg(feature = "profile")]
+pub mod profile;
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
+
+/**
+ * This struct encodes the state of a configuration of sizes up to 31.
+ * To allow for the best performances, we do not use any array types,
+ * and instead encode the information in 32 bits numbers.
+ * One number is used for each property we would like to keep track of.
+ * This results in a very fast execution, even for configuration of size 30,
+ * because most of the program's memory is likely to fit in a CPU cache.
+ *
+ * While the Configuration struct could theoretically be more compact
+ * (when the "taken" flag is 1, the "value" flag becomes useless)
+ * it would be at the detriment of speed.
+ *
+ * We always assume all intermediate values (all except size and value)
+ * are at 0 at the start of an execution, otherwise the program would
+ * lead to undefined behavior. See from_raw for what exactly is undefined.
+ *
+ * The numbers are u32 by default, but can be any Word : with u64 and u128
+ * the sizes go up to 63 and 127, see Configuration64 and
+ * Configuration128. The local function and the executions are written
+ * once for all of them, while the searches and the analyses only exist
+ * for u32.
+ */
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Configuration<W = u32> {
+    // How many bits do we use on each following number?
+    pub size : u32,
+    // Is the value a 0 or a 1?
+    pub value : W,
+    // Is the current symbol from the intermediate alphabet?
+    pub alphabet : W,
+    // Has the symbol been removed using an X?
+    pub taken : W,
+    // Is the local counter odd or even?
+    pub color : W,
+    // Does the local memory contain a 0?
+    pub mem_0 : W,
+    // Does the local memory contain a 1?
+    pub mem_1 : W,
+}
+
+/**
+ * The ways in which two states of an execution can be considered equal.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EqualityStrategy {
+    // Only the value layer is compared, which is what is observable once
+    // the configuration is back to the boolean alphabet. Two states
+    // differing only by their intermediate symbols are considered equal.
+    ValueLayer,
+    // All the layers are compared : value, alphabet, taken, color, mem_0
+    // and mem_1. Two states are equal only if the rest of their executions
+    // are identical.
+    FullState,
+}
+
+/**
+ * A hashable key built by Configuration::state_key. It holds the layers in
+ * the order value, alphabet, taken, color, mem_0, mem_1, with the layers
+ * ignored by the strategy set to 0.
+ */
+pub type StateKey = [u32; 6];
+
+/**
+ * What a configuration with as many 0s as 1s is expected to converge to.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TiePolicy {
+    // The behavior is undefined, so any result is considered correct.
+    Undefined,
+    // The configuration must converge to the given value.
+    ConvergeTo(bool),
+}
+
+/**
+ * The key events of an execution, as returned by Configuration::summarize,
+ * which documents how each of them is detected.
+ */
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExecutionSummary {
+    // the update at which the first intermediate symbol appeared
+    pub kickstart : Option<u32>,
+    // the update at which the head completed its first traversal
+    pub first_traversal : Option<u32>,
+    // the number of color flips during the whole execution
+    pub color_flips : u32,
+    // the number of traversals of the ring completed by the head
+    pub traversals : u32,
+    // the number of updates done
+    pub steps : u32,
+    // the converged value, None if the execution did not converge in time
+    pub value : Option<bool>,
+}
+
+/**
+ * The methods Configuration::detect_cycle_with can use.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CycleDetection {
+    // Remember every state visited in a HashMap. Finds cycles as soon as
+    // they close, but uses memory in proportion to the length of the run.
+    StoreStates,
+    // Brent's algorithm, which only keeps two states.
+    Brent,
+}
+
+/**
+ * The shape of an execution, as returned by Configuration::detect_cycle :
+ * after `tail` updates, the execution either stopped (period 0) or entered
+ * a cycle of `period` updates.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CycleInfo {
+    pub tail : u32,
+    pub period : u32,
+}
+
+impl<W : Word> Configuration<W> {
+    /**
+     * The largest size a configuration can have. Its layers are W
+     * numbers, and the size mask (1 << size) - 1 used all over the place
+     * would overflow for a size of W::BITS, 32 for the default u32.
+     */
+    pub const MAX_SIZE : u32 = W::BITS - 1;
+
+    /**
+     * Panics if the size is above MAX_SIZE. Called before anything is
+     * shifted by the size.
+     */
+    fn assert_size(size : u32) {
+        assert!(size <= Self::MAX_SIZE,
+            "configuration of size {size} is too large, the maximum is {}", Self::MAX_SIZE);
+    }
+
+    /**
+     * Creates a new configuration of a given size and value.
+     * Passing in a value with 1 bits beyond the given size leads to
+     * undefined behavior.
+     *
+     * Panics if the size is above MAX_SIZE, rather than letting the
+     * shifts by the size silently overflow.
+     */
+    pub fn new(value : W, size : u32) -> Self {
+        Self::assert_size(size);
+
+        Self {
+            size, value, ..Default::default()
+        }
+    }
+
+    /**
+     * Returns the value layer of the configuration as a number, masked to
+     * the size of the configuration. On a configuration built with new,
+     * and before any update, this is the value which was passed to new, so
+     * Configuration::new(x.value_code(), x.size) rebuilds x.
+     *
+     * Only the value layer is reflected: intermediate symbols, taken flags,
+     * colors and memories are ignored.
+     */
+    pub fn value_code(&self) -> W {
+        self.value & ((W::ONE << self.size) - W::ONE)
+    }
+
+    /**
+     * Returns the same configuration on wider layers, for instance
+     * Configuration64 from Configuration.
+     */
+    pub fn widen<V : Word + From<W>>(self) -> Configuration<V> {
+        Configuration {
+            size : self.size,
+            value : self.value.into(),
+            alphabet : self.alphabet.into(),
+            taken : self.taken.into(),
+            color : self.color.into(),
+            mem_0 : self.mem_0.into(),
+            mem_1 : self.mem_1.into(),
+        }
+    }
+
+    /**
+     * Prints the configuration to the screen using three lines,
+     * the first indicates the values of the configuration (or X
+     * if it was taken), the second line indicates the value of
+     * the local counter (R or B), and the third line indicates
+     * the value of the local memory (_ for empty, , for {1}, .
+     * for {0}, and ; for {0, 1}). If a symbol is not from the
+     * intermediary alphabet, its spot on the second and third line
+     * are left blank.
+     */
+    pub fn println(&self) {
+        self.write(&mut io::stdout()).expect("could not print the configuration");
+    }
+
+    /**
+     * Writes the configuration with the same three lines as the println
+     * method.
+     */
+    pub fn write(&self, w : &mut dyn Write) -> io::Result<()> {
+        // first line, second line, third line
+        for line in 0..3 {
+            for k in 0..self.size {
+                write!(w, "{}", self.cell_glyphs(k)[line])?;
+            }
+            writeln!(w)?;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Returns the first line printed by the println method : the value of
+     * each cell, or X for taken intermediate cells. For a configuration
+     * with no intermediate symbol, this is the format read by from_str.
+     */
+    pub fn value_string(&self) -> String {
+        let mut line = String::with_capacity(self.size as usize);
+        line.extend((0..self.size).map(|k| self.cell_glyphs(k)[0]));
+        line
+    }
+
+    /**
+     * Returns the three characters printed by the println method for the
+     * cell at the given index : its value (or X), its local counter and its
+     * local memory, from top to bottom.
+     */
+    pub fn cell_glyphs(&self, k : u32) -> [char; 3] {
+        self.cell(k).glyphs()
+    }
+
+    /**
+     * Applies the automata's local function at a given index. As our local
+     * rule is sequential, only one index is updated. The rule is the one
+     * of local_step, run right on the bits of the layers, see apply_rule.
+     * The left parameter is used to indicate which index is at the left
+     * of the current value; this value depends on the size of the configuration
+     * and passing it this way saves a step of computation.
+     */
+    #[inline(always)]
+    pub fn apply_local_function(&mut self, left : u32, index : u32) {
+        apply_rule(&mut Cells { configuration : self, left, index });
+    }
+
+    #[inline(always)]
+    fn layer(&self, layer : Layer) -> W {
+        match layer {
+            Layer::Alphabet => self.alphabet,
+            Layer::Value => self.value,
+            Layer::Taken => self.taken,
+            Layer::Color => self.color,
+            Layer::Mem0 => self.mem_0,
+            Layer::Mem1 => self.mem_1,
+        }
+    }
+
+    #[inline(always)]
+    fn layer_mut(&mut self, layer : Layer) -> &mut W {
+        match layer {
+            Layer::Alphabet => &mut self.alphabet,
+            Layer::Value => &mut self.value,
+            Layer::Taken => &mut self.taken,
+            Layer::Color => &mut self.color,
+            Layer::Mem0 => &mut self.mem_0,
+            Layer::Mem1 => &mut self.mem_1,
+        }
+    }
+
+    /**
+     * Returns the state of the cell at a given index.
+     */
+    #[inline]
+    pub fn cell(&self, index : u32) -> CellState {
+        let mask = W::ONE << index;
+
+        CellState {
+            intermediate : self.alphabet & mask != W::ZERO,
+            value : self.value & mask != W::ZERO,
+            taken : self.taken & mask != W::ZERO,
+            color : self.color & mask != W::ZERO,
+            mem_0 : self.mem_0 & mask != W::ZERO,
+            mem_1 : self.mem_1 & mask != W::ZERO,
+        }
+    }
+
+    /**
+     * Applies the local function on every index in order.
+     * At this step, we can easily define what the "left" index
+     * is and pass it to the apply_local_function method.
+     */
+    pub fn update(&mut self) {
+        self.apply_local_function(self.size - 1, 0);
+
+        for k in 1..self.size {
+            self.apply_local_function(k - 1, k);
+        }
+    }
+
+    /**
+     * Returns the index at the left of a given index, on the ring.
+     */
+    #[inline]
+    pub fn left_of(&self, index : u32) -> u32 {
+        if index == 0 { self.size - 1 } else { index - 1 }
+    }
+
+    /**
+     * Applies the local function on the indices 0 to upto excluded, in
+     * order, and stops there, leaving the rest of the sweep undone. This
+     * shows the state of the configuration in the middle of a sweep.
+     * A full sweep, update_partial(size), is the same as update.
+     */
+    pub fn update_partial(&mut self, upto : u32) {
+        for k in 0..upto.min(self.size) {
+            self.apply_local_function(self.left_of(k), k);
+        }
+    }
+
+    /**
+     * Runs the configuration until it converges, calling f on the initial
+     * state and then after each update. Nothing is stored along the way,
+     * so the memory used does not depend on the length of the execution;
+     * this is the function to use to print or write an execution.
+     *
+     * Like the printing loops it replaces, this never returns if the
+     * configuration does not converge.
+     */
+    pub fn for_each_step(&mut self, f : impl FnMut(&Self)) {
+        self.for_each_step_with::<Uniform>(f);
+    }
+
+    /**
+     * Same as for_each_step, but stops when the given convergence criterion
+     * holds instead of has_converged.
+     */
+    pub fn for_each_step_with<C : Convergence>(&mut self, mut f : impl FnMut(&Self)) {
+        f(self);
+        while ! C::has_converged(self) {
+            self.update();
+            f(self);
+        }
+    }
+
+    /**
+     * Same as for_each_step, but stops at the first error returned by f,
+     * which is then returned. This is what writers use.
+     */
+    pub fn try_for_each_step<E>(&mut self, mut f : impl FnMut(&Self) -> Result<(), E>)
+        -> Result<(), E> {
+        f(self)?;
+        while ! self.has_converged() {
+            self.update();
+            f(self)?;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Returns true if the configuration contains no intermediary symbol
+     * and that all the values are either 0 or 1.
+     *
+     * If the value passed to the new function contained 1 bits beyond the
+     * defined size, this function will return false even if all the bits
+     * within the size are equal.
+     */
+    pub fn has_converged(&self) -> bool {
+        self.alphabet == W::ZERO && // no intermediate symbols
+        (self.value == W::ZERO || self.value == (W::ONE << self.size) - W::ONE)
+        // all values are 0 or all values are 1
+    }
+
+    /**
+     * Returns true if the configuration has converged and one more update
+     * leaves it unchanged in all its layers, including the ones a
+     * converged configuration keeps from its execution, such as taken.
+     * That is, the configuration is a genuine fixed point rather than a
+     * uniform state about to drift away.
+     */
+    pub fn is_converged_stable(&self) -> bool {
+        let mut next = *self;
+        next.update();
+        self.has_converged() && next == *self
+    }
+
+    /**
+     * Updates the configuration until it converges, giving up after
+     * expected_convergence_bound updates like is_correct. Returns true if it converged.
+     */
+    pub fn converge(&mut self) -> bool {
+        let mut iteration_count = 0;
+
+        while ! self.has_converged() {
+            if iteration_count >= expected_convergence_bound(self.size) {
+                return false;
+            }
+
+            self.update();
+            iteration_count += 1;
+        }
+
+        true
+    }
+
+    /**
+     * Updates the configuration until it converges, but at most budget
+     * times, and returns whether it converged along with the part of the
+     * budget left unused. A configuration which has already converged
+     * returns right away with the whole budget. Running an execution with
+     * several budgets one after the other gives the same final state as
+     * running it with their sum, so executions can be interleaved.
+     */
+    pub fn run_with_budget(&mut self, budget : u32) -> (bool, u32) {
+        let mut remaining = budget;
+
+        while ! self.has_converged() {
+            if remaining == 0 {
+                return (false, 0);
+            }
+
+            self.update();
+            remaining -= 1;
+        }
+
+        (true, remaining)
+    }
+
+    /**
+     * Returns true if an update leaves the configuration unchanged. The
+     * configuration itself is not modified.
+     */
+    pub fn is_fixed_point(&self) -> bool {
+        let mut next = *self;
+        next.update();
+        next == *self
+    }
+
+    /**
+     * If our local rule fails to compute the correct density value for
+     * the current configuration, this function returns false.
+     * It does it by computing the real density value of the initial
+     * configuration, and then runs the automata to check if the
+     * two values are coherent.
+     * If the initial configuration had as many 1s than 0s (in the case
+     * of an even size), the function always returns true, as our
+     * automata is then not expected to follow any particular behavior,
+     * and is thus correct.
+     *
+     * See check for a version telling apart the two ways of failing.
+     */
+    pub fn is_correct(&mut self) -> bool {
+        self.check() == Verdict::Correct
+    }
+
+    /**
+     * Same as is_correct, but tells apart a configuration which converged
+     * to the wrong value from one which did not converge at all within the
+     * expected_convergence_bound updates it is given. Since the bound is
+     * not proven, the second case could also be a correct but very slow
+     * execution, which would disprove the bound rather than the rule ;
+     * either way it should be looked at.
+     */
+    pub fn check(&mut self) -> Verdict {
+        #[cfg(feature = "profile")]
+        return profile::time_check(|| self.check_with::<Uniform>());
+
+        #[cfg(not(feature = "profile"))]
+        self.check_with::<Uniform>()
+    }
+
+    /**
+     * Same as check, but with another convergence criterion than the
+     * default has_converged. Once the criterion holds, the value of the
+     * first cell is compared to the majority, so criteria which don't imply
+     * a uniform value layer only make sense for the rules which do.
+     */
+    pub fn check_with<C : Convergence>(&mut self) -> Verdict {
+        self.check_with_ties::<C>(TiePolicy::Undefined)
+    }
+
+    /**
+     * Returns true if the configuration is one of the density extremes
+     * which are known to be correct without running them : at most one
+     * cell differs from all the others.
+     *
+     * Proof sketch, for a lone 1 among n - 1 0s (n >= 3) : the first
+     * update kickstarts a head on the 1, which takes it, and the sweep
+     * scans the rest of the ring with the head's color, the next cell
+     * taking a 0 ; every other cell sees a memory which already holds a 0.
+     * On the next update the head finds a complete memory on its left and
+     * flips its color, and its second traversal can only take 0s, so on
+     * the following update the head sees a memory of 0s only and reverts
+     * to a boolean 0, which propagates around the ring : the configuration
+     * converges to 0. A lone 0 is the same with the values swapped, as the
+     * rule only breaks the symmetry between 0 and 1 on failures. With at
+     * most 2 cells, such configurations are uniform or ties.
+     */
+    pub fn is_trivially_correct(&self) -> bool {
+        let ones = self.value_code().count_ones();
+        ones <= 1 || ones + 1 >= self.size
+    }
+
+    /**
+     * Same as is_correct, but returns true right away on the density
+     * extremes of is_trivially_correct, instead of running them.
+     */
+    pub fn is_correct_fast(&mut self) -> bool {
+        self.is_trivially_correct() || self.is_correct()
+    }
+
+    /**
+     * Same as is_correct, but ties are checked against the given convention
+     * instead of being always considered correct.
+     */
+    pub fn is_correct_with_ties(&mut self, ties : TiePolicy) -> bool {
+        self.check_with_ties::<Uniform>(ties) == Verdict::Correct
+    }
+
+    /**
+     * Same as check_with, but ties are handled according to the given
+     * policy : with a convention, the configuration must converge to the
+     * value it chooses, just like it must converge to the majority
+     * otherwise.
+     */
+    pub fn check_with_ties<C : Convergence>(&mut self, ties : TiePolicy) -> Verdict {
+        let Some(majority) = self.majority(ties) else {
+            return Verdict::Correct; // in case of equality, undefined behavior
+        };
+        let majority = W::from(majority);
+
+        let mut iteration_count = 0;
+
+        while ! C::has_converged(self) {
+
+            if iteration_count >= expected_convergence_bound(self.size) { // We should take around size / 2
+                return Verdict::DidNotConverge;
+            }
+
+            #[cfg(feature = "profile")]
+            profile::time_update(|| self.update());
+            #[cfg(not(feature = "profile"))]
+            self.update();
+            iteration_count += 1;
+        }
+
+        // configuration is uniform, so we only test the first bit
+        if majority == self.value & W::ONE { Verdict::Correct }
+        else { Verdict::WrongValue }
+    }
+
+    /**
+     * Same as check, but also returns the number of updates the execution
+     * took : expected_convergence_bound if it did not converge, and None
+     * for the ties, which are not run.
+     */
+    pub fn check_counting(&mut self) -> (Verdict, Option<u32>) {
+        let Some(majority) = self.majority(TiePolicy::Undefined) else {
+            return (Verdict::Correct, None);
+        };
+
+        let bound = expected_convergence_bound(self.size);
+        let (converged, remaining) = self.run_with_budget(bound);
+        let verdict = if ! converged { Verdict::DidNotConverge }
+            else if W::from(majority) == self.value & W::ONE { Verdict::Correct }
+            else { Verdict::WrongValue };
+
+        (verdict, Some(bound - remaining))
+    }
+
+    /**
+     * Returns the share of the cells, within the size, whose value is 1.
+     */
+    pub fn density(&self) -> f64 {
+        self.value_code().count_ones() as f64 / self.size as f64
+    }
+
+    /**
+     * Returns the value of the majority of the cells. On a tie, returns the
+     * value chosen by the policy, or None if ties are undefined.
+     */
+    pub fn majority(&self, ties : TiePolicy) -> Option<bool> {
+        let count_1 = self.value_code().count_ones();
+        let count_0 = self.size - count_1;
+
+        if count_0 == count_1 {
+            return match ties {
+                TiePolicy::Undefined => None,
+                TiePolicy::ConvergeTo(value) => Some(value),
+            };
+        }
+
+        Some(count_1 > count_0)
+    }
+}
+
+impl Configuration {
+    /**
+     * Creates the configuration alternating 0s and 1s, whose cell of
+     * index 0 has the value start_bit. Every cell disagrees with both of
+     * its neighbors, except on odd sizes where the last cell agrees with
+     * the first. Its density is 1 / 2 on even sizes, which is a tie.
+     *
+     * Panics if the size is above MAX_SIZE, as new.
+     */
+    pub fn alternating(size : u32, start_bit : bool) -> Self {
+        Self::assert_size(size);
+        let pattern = if start_bit { 0x5555_5555 } else { 0xAAAA_AAAA };
+        Self::new(pattern & ((1 << size) - 1), size)
+    }
+
+    /**
+     * Same as new, but the value is read the other way around : its most
+     * significant bit within the size (bit size - 1) is the cell of index 0,
+     * and its bit 0 is the last cell. This is the mirror image of
+     * new(value, size), for representations which number cells from the
+     * left of the written number.
+     *
+     * Panics if the size is above MAX_SIZE, as new. A size of 0 gives the
+     * empty configuration, whatever the value.
+     */
+    pub fn new_msb_first(value : u32, size : u32) -> Self {
+        Self::assert_size(size);
+        // a shift by 32 would overflow
+        let reversed = if size == 0 { 0 } else { value.reverse_bits() >> (32 - size) };
+        Self::new(reversed, size)
+    }
+
+    /**
+     * Builds a configuration from all of its layers, for instance to run
+     * the rule from a state in the middle of an execution, and checks that
+     * it is valid : the size is between 1 and MAX_SIZE, and no layer has
+     * 1 bits beyond the size.
+     *
+     * Any valid state can be updated : the local function reads and writes
+     * the bits of two cells only, and never fails, so update always gives
+     * another valid state. What is undefined, from a state which was not
+     * reached from an initial one, is only the outcome : the execution may
+     * converge to the wrong value, or not converge at all. Note that states
+     * reached from initial ones can have boolean cells with a color, a
+     * memory or a taken flag left over from when they were intermediate,
+     * so these are valid too.
+     */
+    pub fn from_raw(size : u32, value : u32, alphabet : u32, taken : u32, color : u32, mem_0 : u32, mem_1 : u32)
+        -> Result<Self, RawStateError> {
+        let x = Self { size, value, alphabet, taken, color, mem_0, mem_1 };
+        x.validate()?;
+        Ok(x)
+    }
+
+    /**
+     * Checks that the configuration is valid, in the sense of from_raw.
+     */
+    pub fn validate(&self) -> Result<(), RawStateError> {
+        if self.size == 0 || self.size > Self::MAX_SIZE {
+            return Err(RawStateError::InvalidSize(self.size));
+        }
+
+        let layers = [
+            ("value", self.value), ("alphabet", self.alphabet), ("taken", self.taken),
+            ("color", self.color), ("mem_0", self.mem_0), ("mem_1", self.mem_1),
+        ];
+        for (name, layer) in layers {
+            if layer >> self.size != 0 {
+                return Err(RawStateError::BitsBeyondSize(name));
+            }
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Puts the configuration back in an initial state of the same size,
+     * with the given value, so it can be run again without building a new
+     * one. The value has to be passed again because the runs modify it.
+     * Afterwards the configuration is equal to new(value, size) : every
+     * symbol is boolean, nothing is taken and the colors and memories are
+     * all 0.
+     */
+    pub fn reset_to_initial(&mut self, value : u32) {
+        *self = Self::new(value, self.size);
+    }
+
+    /**
+     * Returns a key identifying the state of the configuration under
+     * the given equality strategy: two configurations of the same size
+     * are equal under a strategy if and only if their keys are equal.
+     * The keys can be stored in a HashSet to detect when an execution
+     * comes back to a previous state. The size itself is not part of the
+     * key, since it never changes during an execution.
+     *
+     * All the layers are masked to the size of the configuration, so bits
+     * beyond the size don't make otherwise equal states differ.
+     */
+    pub fn state_key(&self, strategy : EqualityStrategy) -> StateKey {
+        let mask = (1 << self.size) - 1;
+        match strategy {
+            EqualityStrategy::ValueLayer => [self.value & mask, 0, 0, 0, 0, 0],
+            EqualityStrategy::FullState => [
+                self.value & mask,
+                self.alphabet & mask,
+                self.taken & mask,
+                self.color & mask,
+                self.mem_0 & mask,
+                self.mem_1 & mask,
+            ],
+        }
+    }
+
+    /**
+     * Returns how many cells of the configuration have been taken by the
+     * scanning process.
+     */
+    pub fn taken_count(&self) -> u32 {
+        (self.taken & ((1 << self.size) - 1)).count_ones()
+    }
+
+    /**
+     * Returns how far the execution has gone, as the share of the cells,
+     * within the size, which have been taken : taken_count / size.
+     * It is 0 on an initial configuration and never decreases, since cells
+     * are never given back their character (checked by
+     * check_taken_monotone, in the tests).
+     *
+     * It only reaches 1 on some executions : each traversal takes one cell
+     * of each value, and the last one the cell of the majority value which
+     * is left. An initial configuration with m cells of its minority value
+     * thus converges with min(2 m + 1, size) cells taken if m > 0, and
+     * none at all if it is uniform (checked by
+     * check_final_progress, in the tests).
+     */
+    pub fn progress_fraction(&self) -> f64 {
+        self.taken_count() as f64 / self.size as f64
+    }
+
+    /**
+     * Returns the indices, in increasing order, of the cells whose symbol
+     * is from the intermediate alphabet, whether they were taken or not.
+     * These are the cells drawn with a color and a memory by println.
+     */
+    pub fn intermediate_indices(&self) -> Vec<u32> {
+        bit_indices(self.alphabet & ((1 << self.size) - 1))
+    }
+
+    /**
+     * Returns the configuration as a JSON object holding its size and
+     * each of its layers as a number, bit k being the cell of index k.
+     */
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"size\":{},\"value\":{},\"alphabet\":{},\"taken\":{},\"color\":{},\"mem_0\":{},\"mem_1\":{}}}",
+            self.size, self.value, self.alphabet, self.taken, self.color, self.mem_0, self.mem_1,
+        )
+    }
+
+    /**
+     * Returns a short token identifying the initial configuration with
+     * this size and value layer, to be pasted in a message and read back
+     * with from_token. The token is the standard base64 encoding of the
+     * size as one byte, followed by the value masked to the size, in as
+     * few little endian bytes as it needs. The other layers are not part
+     * of the token.
+     */
+    pub fn to_token(&self) -> String {
+        let mut bytes = vec![self.size as u8];
+        bytes.extend_from_slice(&self.value_code().to_le_bytes()[..self.size.div_ceil(8) as usize]);
+
+        let mut token = String::new();
+        for chunk in bytes.chunks(3) {
+            let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+            for i in 0..4 {
+                token.push(if i <= chunk.len() {
+                    BASE64_ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char
+                }
+                else {
+                    '='
+                });
+            }
+        }
+
+        token
+    }
+
+    /**
+     * Reads a token written by to_token back into a new configuration.
+     * The token must be valid base64, with padding only at its end, the
+     * size between 1 and MAX_SIZE, the
+     * number of bytes the one expected for that size, and the value must
+     * not have 1 bits beyond the size.
+     */
+    pub fn from_token(token : &str) -> Result<Self, TokenError> {
+        let token = token.trim();
+        if token.is_empty() || ! token.len().is_multiple_of(4) {
+            return Err(TokenError::InvalidBase64);
+        }
+
+        let mut bytes = vec![];
+        let chunks = token.len() / 4;
+        for (number, chunk) in token.as_bytes().chunks(4).enumerate() {
+            let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+            if padding > 2 || (padding > 0 && number + 1 < chunks) {
+                return Err(TokenError::InvalidBase64);
+            }
+
+            let mut n = 0u32;
+            for &c in &chunk[..4 - padding] {
+                let Some(digit) = BASE64_ALPHABET.iter().position(|&d| d == c) else {
+                    return Err(TokenError::InvalidBase64);
+                };
+                n = n << 6 | digit as u32;
+            }
+            n <<= 6 * padding;
+
+            bytes.extend_from_slice(&n.to_be_bytes()[1..4 - padding]);
+        }
+
+        let size = bytes[0] as u32;
+        if size == 0 || size > Self::MAX_SIZE {
+            return Err(TokenError::InvalidSize(size));
+        }
+        if bytes.len() != 1 + size.div_ceil(8) as usize {
+            return Err(TokenError::WrongLength(bytes.len()));
+        }
+
+        let value = bytes[1..].iter().rev().fold(0u32, |value, &b| value << 8 | b as u32);
+        if value >> size != 0 {
+            return Err(TokenError::ValueBeyondSize);
+        }
+
+        Ok(Self::new(value, size))
+    }
+
+    /**
+     * Returns which case of apply_local_function would fire at a given
+     * index, without applying it. This is only used to explain
+     * executions, never on the search path.
+     */
+    pub fn rule_branch(&self, left : u32, index : u32) -> RuleBranch {
+        local_branch(self.cell(left), self.cell(index))
+    }
+
+    /**
+     * Same as update, but also returns, for each index, which case of the
+     * local function fired when it was updated.
+     */
+    pub fn update_traced(&mut self) -> Vec<RuleBranch> {
+        let mut branches = Vec::with_capacity(self.size as usize);
+
+        for k in 0..self.size {
+            let left = self.left_of(k);
+            branches.push(self.rule_branch(left, k));
+            self.apply_local_function(left, k);
+        }
+
+        branches
+    }
+
+    /**
+     * Same as update, but returns, in increasing order, the indices of the
+     * cells whose state changed during the update, in any of the layers.
+     */
+    pub fn update_with_changes(&mut self) -> Vec<u32> {
+        let before = *self;
+        self.update();
+
+        let changed = (before.value ^ self.value) | (before.alphabet ^ self.alphabet) |
+            (before.taken ^ self.taken) | (before.color ^ self.color) |
+            (before.mem_0 ^ self.mem_0) | (before.mem_1 ^ self.mem_1);
+        bit_indices(changed & ((1 << self.size) - 1))
+    }
+
+    /**
+     * Runs the configuration with update_traced until it converges, and
+     * distills the execution into a few key events, read from the cases of
+     * the local function that fired at each step :
+     *  - the kickstart is the first update in which a Kickstart fired, that
+     *    is the first update creating an intermediate symbol ;
+     *  - the head is a cell whose left neighbour is intermediate and of the
+     *    same color, and it completes its first traversal of the ring at the
+     *    first update in which it fires a ColorFlip or a revert to boolean,
+     *    since both only happen once the memory has seen the whole ring ;
+     *  - the color flips are all the ColorFlip cases fired ;
+     *  - the head completes a traversal at each color flip, and the last
+     *    one when it first reverts to boolean, so the traversals are the
+     *    color flips, plus one if the head reverted ;
+     *  - the value is the one the configuration converged to.
+     *
+     * Steps count the updates, starting at 1 for the first one. Like
+     * is_correct, gives up after expected_convergence_bound updates, in
+     * which case the value is None and the other events are those seen
+     * so far.
+     */
+    pub fn summarize(&mut self) -> ExecutionSummary {
+        let mut summary = ExecutionSummary::default();
+        let mut reverted = false;
+        let mut step = 0;
+
+        while ! self.has_converged() {
+            if step >= expected_convergence_bound(self.size) {
+                summary.steps = step;
+                return summary;
+            }
+
+            let branches = self.update_traced();
+            step += 1;
+
+            for branch in branches {
+                match branch {
+                    RuleBranch::Kickstart => {
+                        summary.kickstart.get_or_insert(step);
+                    },
+                    RuleBranch::ColorFlip => {
+                        summary.first_traversal.get_or_insert(step);
+                        summary.color_flips += 1;
+                    },
+                    RuleBranch::RevertToOne | RuleBranch::RevertToZero => {
+                        summary.first_traversal.get_or_insert(step);
+                        reverted = true;
+                    },
+                    _ => {},
+                }
+            }
+            summary.traversals = summary.color_flips + reverted as u32;
+        }
+
+        summary.steps = step;
+        summary.value = Some(self.value & 1 != 0);
+        summary
+    }
+
+    /**
+     * Runs the configuration until it converges and returns its taken_count
+     * at each step, starting with the initial state.
+     */
+    pub fn taken_history(&mut self) -> Vec<u32> {
+        let mut history = vec![];
+        self.for_each_step(|x| history.push(x.taken_count()));
+        history
+    }
+
+    /**
+     * Runs the configuration until it converges, for at most
+     * expected_convergence_bound updates, and checks that its taken_count
+     * never decreases. Returns the first step at which it does, with the
+     * configuration at that step, or None if it never does.
+     *
+     * No reset is allowed : the rule sets taken flags when a character is
+     * consumed and never clears them, not even when the cell reverts to
+     * boolean, where the flag is simply no longer read. A new traversal
+     * after a color flip only resets the memories.
+     */
+    pub fn find_taken_drop(&mut self) -> Option<(u32, Configuration)> {
+        let mut previous = self.taken_count();
+        let mut step = 0;
+
+        while ! self.has_converged() && step < expected_convergence_bound(self.size) {
+            self.update();
+            step += 1;
+
+            let count = self.taken_count();
+            if count < previous {
+                return Some((step, *self));
+            }
+            previous = count;
+        }
+
+        None
+    }
+
+    /**
+     * Returns true if all the values of the configuration are equal, without
+     * looking at the other layers. Unlike has_converged, this can be true
+     * while some symbols are still intermediate.
+     */
+    pub fn has_uniform_value(&self) -> bool {
+        let mask = (1 << self.size) - 1;
+        self.value & mask == 0 || self.value & mask == mask
+    }
+
+    /**
+     * Runs the configuration and returns the first step (0 being the initial
+     * state) at which its value layer is uniform. Since the rule defaults
+     * to 0 on failure, this can happen before has_converged becomes true,
+     * while intermediate symbols are still around (0110100 has a uniform
+     * value layer after 5 updates, but only converges after 6); it is
+     * never later.
+     * Like is_correct, gives up and returns None after
+     * expected_convergence_bound updates.
+     */
+    pub fn first_uniform_value_step(&mut self) -> Option<u32> {
+        let mut step = 0;
+
+        while ! self.has_uniform_value() {
+            if step >= expected_convergence_bound(self.size) {
+                return None;
+            }
+
+            self.update();
+            step += 1;
+        }
+
+        Some(step)
+    }
+
+    /**
+     * Runs the configuration for at most cap updates, and stops as soon as
+     * it converges or comes back to a state it already visited. States are
+     * compared with state_key under the FullState strategy. This uses
+     * Brent's algorithm, so the memory used does not depend on cap.
+     *
+     * If the configuration converged, or if the cap was reached first, the
+     * returned period is 0 and the tail is the number of updates done.
+     * Otherwise the tail is the number of updates before the cycle, and the
+     * period the number of updates of the cycle.
+     * A correct rule always converges, so a non zero period is a bug.
+     */
+    pub fn detect_cycle(&mut self, cap : u32) -> CycleInfo {
+        self.detect_cycle_with::<Sequential>(cap, CycleDetection::Brent)
+    }
+
+    /**
+     * Same as detect_cycle, but under any rule and with the chosen method.
+     * Both methods find the same tail and period, but Brent's algorithm
+     * may need up to twice as many updates as the length of the tail plus
+     * the period to notice a cycle, so it can hit a cap the other method
+     * would not have reached. The configuration is left in some state of
+     * the execution.
+     */
+    pub fn detect_cycle_with<R : Rule>(&mut self, cap : u32, method : CycleDetection) -> CycleInfo {
+        match method {
+            CycleDetection::StoreStates => self.detect_cycle_storing::<R>(cap),
+            CycleDetection::Brent => self.detect_cycle_brent::<R>(cap),
+        }
+    }
+
+    /**
+     * Cycle detection remembering every state visited, with the step at
+     * which it was first seen.
+     */
+    fn detect_cycle_storing<R : Rule>(&mut self, cap : u32) -> CycleInfo {
+        let mut seen = HashMap::new();
+        let mut step = 0;
+
+        while ! R::has_converged(self) && step < cap {
+            if let Some(first) = seen.insert(self.state_key(EqualityStrategy::FullState), step) {
+                return CycleInfo { tail : first, period : step - first };
+            }
+
+            R::update(self);
+            step += 1;
+        }
+
+        CycleInfo { tail : step, period : 0 }
+    }
+
+    /**
+     * Runs the configuration until it converges, or for at most cap updates,
+     * and returns the number of distinct states it went through, the
+     * initial and last ones included. States are compared with state_key
+     * under the FullState strategy, as in detect_cycle.
+     *
+     * An execution which converges after n updates never comes back to a
+     * state, so it visits exactly n + 1 distinct states. An execution which
+     * cycles visits fewer distinct states than the number of updates done
+     * plus one, which makes this an anomaly signal.
+     */
+    pub fn distinct_states_visited(&mut self, cap : u32) -> usize {
+        let mut seen = HashSet::new();
+        let mut step = 0;
+
+        seen.insert(self.state_key(EqualityStrategy::FullState));
+        while ! self.has_converged() && step < cap {
+            self.update();
+            step += 1;
+            seen.insert(self.state_key(EqualityStrategy::FullState));
+        }
+
+        seen.len()
+    }
+
+    /**
+     * Cycle detection with Brent's algorithm : a hare runs ahead, and the
+     * tortoise teleports to the hare each time the number of steps since
+     * its last teleport reaches a power of two, until they meet. Their
+     * distance is then the period, and the tail is found by running two
+     * configurations, one period apart, from the start until they meet.
+     */
+    fn detect_cycle_brent<R : Rule>(&mut self, cap : u32) -> CycleInfo {
+        let key = |x : &Configuration| x.state_key(EqualityStrategy::FullState);
+        let start = *self;
+
+        let mut tortoise = start;
+        let mut power = 1;
+        let mut period = 1;
+        let mut step = 0;
+
+        loop {
+            if R::has_converged(self) || step >= cap {
+                return CycleInfo { tail : step, period : 0 };
+            }
+
+            R::update(self);
+            step += 1;
+
+            if key(&tortoise) == key(self) {
+                break;
+            }
+            if power == period {
+                tortoise = *self;
+                power *= 2;
+                period = 0;
+            }
+            period += 1;
+        }
+
+        let mut tortoise = start;
+        *self = start;
+        for _ in 0..period {
+            R::update(self);
+        }
+
+        let mut tail = 0;
+        while key(&tortoise) != key(self) {
+            R::update(&mut tortoise);
+            R::update(self);
+            tail += 1;
+        }
+
+        CycleInfo { tail, period }
+    }
+
+    /**
+     * Runs the configuration and records, for the initial state and after
+     * each update, whether its value layer is uniform (has_uniform_value).
+     * Stops once it has converged, or after cap updates, so the history
+     * holds at most cap + 1 entries.
+     */
+    pub fn value_uniformity_history(&mut self, cap : u32) -> Vec<bool> {
+        let mut history = vec![self.has_uniform_value()];
+
+        for _ in 0..cap {
+            if self.has_converged() {
+                break;
+            }
+            self.update();
+            history.push(self.has_uniform_value());
+        }
+
+        history
+    }
+
+    /**
+     * Returns true if, within cap updates, the value layer of the
+     * configuration becomes uniform and then stops being uniform again.
+     * This would be surprising, since the rule only makes the value layer
+     * uniform at the very end of an execution.
+     */
+    pub fn overshoots_uniformity(&mut self, cap : u32) -> bool {
+        self.value_uniformity_history(cap)
+            .windows(2)
+            .any(|w| w[0] && ! w[1])
+    }
+
+    /**
+     * Returns the number of cells, within the size, whose values differ
+     * between the two configurations. Only the value layer is compared.
+     */
+    pub fn value_hamming(&self, other : &Configuration) -> u32 {
+        ((self.value ^ other.value) & ((1 << self.size) - 1)).count_ones()
+    }
+
+    /**
+     * Experimental: tries to predict the value this initial configuration
+     * converges to without running it to convergence, by reasoning about
+     * the movement of the scanning head. Returns None when it cannot decide,
+     * in which case the configuration has to be simulated.
+     *
+     * The first update kickstarts a single head at the first index whose
+     * value differs from its left neighbor, and the head scans the whole
+     * rest of the ring in that same sweep. If the state after this sweep has
+     * exactly this shape (boolean cells, then one block of intermediate
+     * cells of the same color running up to the end of the ring), then each
+     * of the following traversals of the head cancels one 0 and one 1, and
+     * the head reverts to the value of which some are left once the other
+     * is exhausted. That value is the majority among the untaken cells plus
+     * the characters held in the memory of the head, which is what is
+     * returned. Uniform configurations are their own prediction, and ties
+     * are never decided.
+     *
+     * This is not used by any search. Use find_prediction_mismatch to check
+     * it against the real execution.
+     */
+    pub fn predict_majority(&self) -> Option<bool> {
+        let mask = (1 << self.size) - 1;
+        let value = self.value & mask;
+        if value == 0 || value == mask {
+            return Some(value != 0);
+        }
+
+        let mut x = Configuration::new(value, self.size);
+        x.update();
+
+        // the head must start at the first cell differing from its left neighbor
+        let start = (0..self.size).find(|&k| (value >> k & 1) != (value >> self.left_of(k) & 1))
+            .unwrap_or(0);
+        let head_mask = mask & !((1 << start) - 1);
+        if x.alphabet != head_mask || x.color != 0 {
+            return None;
+        }
+
+        let last = 1 << (self.size - 1);
+        let untaken = mask & !x.taken;
+        let zeros = (untaken & !x.value).count_ones() + (x.mem_0 & last != 0) as u32;
+        let ones = (untaken & x.value).count_ones() + (x.mem_1 & last != 0) as u32;
+
+        if zeros == ones { None } else { Some(ones > zeros) }
+    }
+}
+
+/**
+ * A criterion telling when an execution is over. The step loops which take
+ * one (check_with, for_each_step_with) default to Uniform.
+ */
+pub trait Convergence {
+    fn has_converged<W : Word>(x : &Configuration<W>) -> bool;
+}
+
+/**
+ * The criterion of the paper : no intermediate symbol is left and all the
+ * values are equal, see Configuration::has_converged.
+ */
+pub struct Uniform;
+
+impl Convergence for Uniform {
+    #[inline]
+    fn has_converged<W : Word>(x : &Configuration<W>) -> bool {
+        x.has_converged()
+    }
+}
+
+/**
+ * The execution is over as soon as it reaches any fixed point of the rule
+ * of the paper, uniform or not. This is one more update per check.
+ */
+pub struct FixedPoint;
+
+impl Convergence for FixedPoint {
+    fn has_converged<W : Word>(x : &Configuration<W>) -> bool {
+        x.is_fixed_point()
+    }
+}
+
+/**
+ * A way of updating a whole configuration. The rule of the paper is
+ * Sequential; other rules exist to compare it against.
+ */
+pub trait Rule {
+    /**
+     * The name of the rule, as recorded with the runs of ResultsDb.
+     */
+    const NAME : &'static str;
+
+    /**
+     * Applies one step of the rule on the configuration.
+     */
+    fn update(x : &mut Configuration);
+
+    /**
+     * The convergence criterion of the rule. By default, a configuration
+     * has converged when Configuration::has_converged says so.
+     */
+    fn has_converged(x : &Configuration) -> bool {
+        Uniform::has_converged(x)
+    }
+
+    /**
+     * Runs the rule on the configuration until it converges, and returns
+     * the value it converged to. Like is_correct, gives up and returns None
+     * if the configuration has not converged after expected_convergence_bound
+     * steps.
+     */
+    fn converged_value(x : &mut Configuration) -> Option<bool> {
+        let mut iteration_count = 0;
+
+        while ! Self::has_converged(x) {
+            if iteration_count >= expected_convergence_bound(x.size) {
+                return None;
+            }
+
+            Self::update(x);
+            iteration_count += 1;
+        }
+
+        Some(x.value & 1 != 0)
+    }
+}
+
+/**
+ * The rule of the paper : the local function is applied on each index in
+ * order, each cell seeing the already updated state of its left neighbor.
+ */
+pub struct Sequential;
+
+impl Rule for Sequential {
+    const NAME : &'static str = "sequential";
+
+    fn update(x : &mut Configuration) {
+        x.update();
+    }
+}
+
+/**
+ * The same local function, but applied on all the cells at once : each
+ * cell is updated from the state of the configuration before the step.
+ */
+pub struct Synchronous;
+
+impl Rule for Synchronous {
+    const NAME : &'static str = "synchronous";
+
+    fn update(x : &mut Configuration) {
+        let before = *x;
+
+        for k in 0..x.size {
+            let left = x.left_of(k);
+            let mut cell = before;
+            cell.apply_local_function(left, k);
+
+            let mask = 1 << k;
+            x.value = x.value & !mask | cell.value & mask;
+            x.alphabet = x.alphabet & !mask | cell.alphabet & mask;
+            x.taken = x.taken & !mask | cell.taken & mask;
+            x.color = x.color & !mask | cell.color & mask;
+            x.mem_0 = x.mem_0 & !mask | cell.mem_0 & mask;
+            x.mem_1 = x.mem_1 & !mask | cell.mem_1 & mask;
+        }
+    }
+}
+
+/**
+ * Runs every configuration of a given size under both rules, in parallel,
+ * and returns in increasing order the configurations on which they
+ * disagree : either they converged to different values, or only one of
+ * them converged. Both halves of the configurations are tested, since
+ * the rules need not be symmetric.
+ */
+#[cfg(feature = "search")]
+pub fn rules_agree<R1 : Rule, R2 : Rule>(size : u32) -> Vec<u32> {
+    (0..1u32 << size)
+        .into_par_iter()
+        .filter(|&k| {
+            let first = R1::converged_value(&mut Configuration::new(k, size));
+            let second = R2::converged_value(&mut Configuration::new(k, size));
+            first != second
+        })
+        .collect()
+}
+
+/**
+ * The reasons why a configuration built with from_raw can be invalid.
+ */
+#[derive(Debug, PartialEq, Eq)]
+pub enum RawStateError {
+    // The size is 0 or above Configuration::MAX_SIZE.
+    InvalidSize(u32),
+    // The named layer has 1 bits beyond the size.
+    BitsBeyondSize(&'static str),
+}
+
+impl std::fmt::Display for RawStateError {
+    fn fmt(&self, f : &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::InvalidSize(size) => write!(f, "invalid size {size}"),
+            Self::BitsBeyondSize(layer) => write!(f, "the {layer} layer has cells beyond the size"),
+        }
+    }
+}
+
+/**
+ * The digits of the standard base64 encoding, used by the tokens of
+ * Configuration::to_token.
+ */
+const BASE64_ALPHABET : &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/**
+ * The errors which can happen while reading a configuration from a token.
+ */
+#[derive(Debug, PartialEq, Eq)]
+pub enum TokenError {
+    // The token is not made of groups of four base64 digits.
+    InvalidBase64,
+    // The size is 0 or above Configuration::MAX_SIZE.
+    InvalidSize(u32),
+    // The token does not hold as many bytes as its size needs.
+    WrongLength(usize),
+    // The value has 1 bits beyond the size.
+    ValueBeyondSize,
+}
+
+impl std::fmt::Display for TokenError {
+    fn fmt(&self, f : &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::InvalidBase64 => write!(f, "invalid base64 token"),
+            Self::InvalidSize(size) => write!(f, "invalid size {size} in token"),
+            Self::WrongLength(length) => write!(f, "token of {length} bytes does not match its size"),
+            Self::ValueBeyondSize => write!(f, "token value has cells beyond its size"),
+        }
+    }
+}
+
+/**
+ * The errors which can happen while parsing a configuration from a string.
+ */
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseConfigurationError {
+    // The string contained no cell at all.
+    Empty,
+    // The string has more cells than a configuration can hold.
+    TooLong(usize),
+    // The string contained something else than a 0 or a 1.
+    InvalidCharacter(char),
+}
+
+impl std::error::Error for ParseConfigurationError {}
+
+impl std::fmt::Display for ParseConfigurationError {
+    fn fmt(&self, f : &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty configuration"),
+            Self::TooLong(size) => write!(f, "configuration of size {size} is too long"),
+            Self::InvalidCharacter(c) => write!(f, "invalid character {c:?} in configuration"),
+        }
+    }
+}
+
+/**
+ * Parses a configuration from a string of 0s and 1s, the first character
+ * being the cell of index 0. This is the same order as the first line
+ * printed by the println method, so a printed initial configuration can
+ * be pasted back as is. Surrounding whitespace is ignored.
+ */
+impl FromStr for Configuration {
+    type Err = ParseConfigurationError;
+
+    fn from_str(s : &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(ParseConfigurationError::Empty);
+        }
+        if s.chars().count() > Configuration::<u32>::MAX_SIZE as usize {
+            return Err(ParseConfigurationError::TooLong(s.chars().count()));
+        }
+
+        let mut value = 0;
+        let mut size = 0;
+        for c in s.chars() {
+            match c {
+                '0' => {},
+                '1' => value |= 1 << size,
+                _ => return Err(ParseConfigurationError::InvalidCharacter(c)),
+            }
+            size += 1;
+        }
+
+        Ok(Configuration::new(value, size))
+    }
+}
+
+/**
+ * The number of updates within which every configuration of the given size
+ * is expected to converge, and after which is_correct and the other
+ * bounded runs give up : size + 1.
+ *
+ * The paper claims a convergence in about size / 2 updates : the head
+ * cancels a 0 and a 1 on each traversal of the ring, and the sequential
+ * sweep carries it around the ring within a single update. On top of
+ * that, one update kickstarts the head, and a couple more revert it to
+ * boolean and propagate the result around the ring. The slowest
+ * configurations, a single block of size / 2 1s, take exactly
+ * size / 2 + 3 updates on all sizes from 3 to 18 (the tests check it up
+ * to 14), but that is only observed, not proven, so
+ * the bound keeps a margin of about size / 2 updates above it rather than
+ * reporting slow but correct executions as not converging.
+ */
+pub fn expected_convergence_bound(size : u32) -> u32 {
+    size + 1
+}
+
+/**
+ * The outcome of running Configuration::check on a configuration.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Correct,
+    // The configuration converged, but not to its majority value.
+    WrongValue,
+    // The configuration did not converge within the bound of is_correct.
+    DidNotConverge,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ops::Range;
+    use std::path::PathBuf;
+
+    #[cfg(feature = "search")]
+    use crate::analysis::FixedPopcount;
+    use crate::corpus::corpus_line;
+    use crate::invariants::{cell_from_description, check_uniform_fixed_points, check_uniform_regions};
+    #[cfg(feature = "search")]
+    use crate::sampling::density_share;
+    #[cfg(feature = "search")]
+    use crate::search::gray_code;
+
+    #[test]
+    fn corpus_lines_start_at_cell_zero() {
+        assert_eq!(corpus_line(5, 0b00011), "11000");
+        assert_eq!(read_corpus_line(&corpus_line(9, 0b100110101)), (9, 0b100110101));
+    }
+
+    fn read_corpus_line(line : &str) -> (u32, u32) {
+        let x = Configuration::<u32>::from_str(line).unwrap();
+        (x.size, x.value)
+    }
+
+    #[test]
+    #[cfg(feature = "search")]
+    fn convergence_maps_survive_their_format() {
+        let mut map = convergence_map(7);
+        map.did_not_converge[1] |= 1 << 3;
+        let mut bytes = vec![];
+        map.write_to(&mut bytes).unwrap();
+        let read = ConvergenceMap::read_from(&mut &bytes[..]).unwrap();
+
+        assert_eq!(read, map);
+        assert_eq!(read.value(64 + 3), None);
+        assert_eq!(read.value(0b0000111), Some(false));
+        assert_eq!(read.value(0b0001111), Some(true));
+        assert_eq!(convergence_map(7).diff(&read), vec![(64 + 3, MapChange::ConvergedToNone)]);
+    }
+
+    #[test]
+    fn configurations_which_did_not_converge_are_never_clean() {
+        let words = 2;
+        let mut map = ConvergenceMap { size : 7, values : vec![0; words], ties : vec![0; words],
+            did_not_converge : vec![0; words] };
+        // a majority of 0s, stored as converging to 0
+        assert!(map.is_clean(0b0000011));
+        map.did_not_converge[0] |= 1 << 0b0000011 | 1 << 0b0000001;
+        assert!(! map.is_clean(0b0000011));
+        assert!(! map.is_clean(0b0000001));
+        assert!(map.is_clean(0b0000010));
+        // a majority of 1s converging to 0
+        assert!(! map.is_clean(0b0001111));
+    }
+
+    #[test]
+    fn slowest_configurations_take_half_the_size_and_three_updates() {
+        for size in 3..=14 {
+            let steps = |value : u32| {
+                let mut x = Configuration::new(value, size);
+                let (converged, remaining) = x.run_with_budget(expected_convergence_bound(size));
+                assert!(converged, "{value:#X} of size {size} does not converge");
+                expected_convergence_bound(size) - remaining
+            };
+            // a block of size / 2 1s, the first of them on cell 1
+            let block = ((1 << (size / 2)) - 1) << 1;
+
+            assert_eq!(steps(block), size / 2 + 3, "size {size}");
+            assert_eq!((0..1 << size).map(steps).max(), Some(size / 2 + 3), "size {size}");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "search")]
+    fn prediction_mismatches_use_the_bound_of_is_correct() {
+        for size in 2..=10 {
+            assert_eq!(find_prediction_mismatch(size), None, "size {size}");
+        }
+    }
+
+    /**
+     * Packs two cells as the cells 0 and 1 of a configuration.
+     */
+    fn neighborhood(left : CellState, current : CellState) -> Configuration {
+        let layer = |flag : fn(CellState) -> bool| flag(left) as u32 | (flag(current) as u32) << 1;
+
+        Configuration {
+            size : 2,
+            alphabet : layer(|cell| cell.intermediate),
+            value : layer(|cell| cell.value),
+            taken : layer(|cell| cell.taken),
+            color : layer(|cell| cell.color),
+            mem_0 : layer(|cell| cell.mem_0),
+            mem_1 : layer(|cell| cell.mem_1),
+        }
+    }
+
+    /**
+     * Checks that the cells described by left and current take the given
+     * branch of the local function, and that current ends as described by
+     * after, both with local_step and in a configuration.
+     */
+    fn assert_branch(branch : RuleBranch, left : &str, current : &str, after : &str) {
+        let (left, current, after) =
+            (cell_from_description(left), cell_from_description(current), cell_from_description(after));
+
+        assert_eq!(local_branch(left, current), branch);
+        assert_eq!(local_step(left, current), after);
+
+        let mut x = neighborhood(left, current);
+        assert_eq!(x.rule_branch(0, 1), branch);
+        x.apply_local_function(0, 1);
+        assert_eq!((x.cell(0), x.cell(1)), (left, after));
+    }
+
+    #[test]
+    fn equal_booleans_are_stable() {
+        assert_branch(RuleBranch::Stable, "b1-", "b1-", "b1-");
+        assert_branch(RuleBranch::Stable, "b0-", "b0-", "b0-");
+    }
+
+    #[test]
+    fn different_booleans_kickstart_a_head() {
+        assert_branch(RuleBranch::Kickstart, "b0-", "b1-", "i1XB,");
+        assert_branch(RuleBranch::Kickstart, "b1-", "b0-", "i0XB.");
+    }
+
+    #[test]
+    fn booleans_propagate_over_intermediate_cells() {
+        assert_branch(RuleBranch::Propagation, "b1-", "i0XB_", "b1X");
+        assert_branch(RuleBranch::Propagation, "b0-", "i1XR;", "b0XR;");
+    }
+
+    #[test]
+    fn scanning_takes_missing_characters() {
+        assert_branch(RuleBranch::Scanning, "i0XR.", "b1-", "i1XR;");
+        assert_branch(RuleBranch::Scanning, "i1XB,", "i0-R_", "i0XB;");
+    }
+
+    #[test]
+    fn scanning_leaves_taken_and_known_characters() {
+        assert_branch(RuleBranch::Scanning, "i0XR.", "i0XB,", "i0XR.");
+        assert_branch(RuleBranch::Scanning, "i0XR.", "b0-", "i0-R.");
+    }
+
+    #[test]
+    fn complete_memories_flip_the_color() {
+        assert_branch(RuleBranch::ColorFlip, "i0XB;", "i0XB;", "i0XR_");
+        assert_branch(RuleBranch::ColorFlip, "i1XR;", "i1XR,", "i1XB_");
+    }
+
+    #[test]
+    fn memories_of_1s_revert_to_one() {
+        assert_branch(RuleBranch::RevertToOne, "i0XB,", "i0XB_", "b1X");
+    }
+
+    #[test]
+    fn other_memories_revert_to_zero() {
+        assert_branch(RuleBranch::RevertToZero, "i0XB.", "i1XB_", "b0X");
+        assert_branch(RuleBranch::RevertToZero, "i0XR_", "i1XR,", "b0XR,");
+    }
+
+    #[test]
+    fn msb_first_configurations_are_mirror_images() {
+        for size in 1..=10 {
+            for value in 0..1 << size {
+                let x = Configuration::new(value, size);
+                let y = Configuration::new_msb_first(value, size);
+
+                assert!((0..size).all(|k| y.cell(k) == x.cell(size - 1 - k)), "{value:#X} of size {size}");
+            }
+        }
+        assert_eq!(Configuration::new_msb_first(0b1101, 4).value, 0b1011);
+        assert_eq!(Configuration::new_msb_first(0b0001, 31).value, 1 << 30);
+    }
+
+    #[test]
+    fn msb_first_configurations_of_size_0_are_empty() {
+        let x = Configuration::new_msb_first(u32::MAX, 0);
+        assert_eq!((x.size, x.value), (0, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "configuration of size 32 is too large")]
+    fn msb_first_configurations_check_their_size() {
+        Configuration::new_msb_first(1, 32);
+    }
+
+    /**
+     * Checks the alternating configurations of a given size, starting with
+     * either value : they must have the expected density, be ties on even
+     * sizes and otherwise converge to their majority, that is the value of
+     * their cell of index 0. Returns the value of the first one which does
+     * not, if any.
+     */
+    fn check_alternating(size : u32) -> Option<u32> {
+        [false, true].into_iter().map(|start_bit| Configuration::alternating(size, start_bit))
+            .find(|x| {
+                let ones = if x.value & 1 != 0 { size.div_ceil(2) } else { size / 2 };
+                let majority = x.majority(TiePolicy::Undefined);
+                let expected = if size.is_multiple_of(2) { None } else { Some(x.value & 1 != 0) };
+                let mut run = *x;
+
+                x.density() != ones as f64 / size as f64 || majority != expected || ! run.is_correct()
+            })
+            .map(|x| x.value)
+    }
+
+    #[test]
+    fn alternating_configurations_alternate() {
+        assert_eq!(Configuration::alternating(6, true).value, 0b010101);
+        assert_eq!(Configuration::alternating(6, false).value, 0b101010);
+        assert_eq!(Configuration::alternating(31, true).value, 0x5555_5555 & ((1 << 31) - 1));
+
+        for size in 2..=16 {
+            for start_bit in [false, true] {
+                let mut x = Configuration::alternating(size, start_bit);
+                let ones = if start_bit { size.div_ceil(2) } else { size / 2 };
+
+                assert_eq!(x.value.count_ones(), ones, "size {size}");
+                assert_eq!(x.majority(TiePolicy::Undefined).is_none(), size % 2 == 0, "size {size}");
+                assert!(x.is_correct(), "size {size}");
+            }
+            assert_eq!(check_alternating(size), None, "size {size}");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "configuration of size 40 is too large")]
+    fn alternating_configurations_check_their_size() {
+        Configuration::alternating(40, true);
+    }
+
+    /**
+     * Checks that every configuration of a given size comes back unchanged
+     * from its token. Returns the value of the first one which does not, if
+     * any.
+     */
+    fn check_token_round_trip(size : u32) -> Option<u32> {
+        (0..1 << size).find(|&value| {
+            let x = Configuration::new(value, size);
+            Configuration::from_token(&x.to_token()) != Ok(x)
+        })
+    }
+
+    #[test]
+    fn tokens_survive_a_round_trip() {
+        for size in 1..=Configuration::<u32>::MAX_SIZE {
+            for value in [0, 1, 0x16, 0x5555_5555, u32::MAX] {
+                let x = Configuration::new(value & ((1 << size) - 1), size);
+                assert_eq!(Configuration::from_token(&x.to_token()), Ok(x), "{:#X} of size {size}", x.value);
+            }
+        }
+        assert_eq!(Configuration::new(0b0010110, 7).to_token(), "BxY=");
+
+        for size in 2..=12 {
+            assert_eq!(check_token_round_trip(size), None, "size {size}");
+        }
+    }
+
+    #[test]
+    fn malformed_tokens_are_rejected() {
+        let from_token = Configuration::<u32>::from_token;
+
+        // the two bytes of BxY=, each padded on its own
+        assert_eq!(from_token("Bw==Fg=="), Err(TokenError::InvalidBase64));
+        assert_eq!(from_token("Bw=A"), Err(TokenError::InvalidBase64));
+        assert_eq!(from_token("B==="), Err(TokenError::InvalidBase64));
+        assert_eq!(from_token("BxY"), Err(TokenError::InvalidBase64));
+        assert_eq!(from_token("Bx!="), Err(TokenError::InvalidBase64));
+        assert_eq!(from_token(""), Err(TokenError::InvalidBase64));
+        assert_eq!(from_token("AAA="), Err(TokenError::InvalidSize(0)));
+        assert_eq!(from_token("BxYA"), Err(TokenError::WrongLength(3)));
+        assert_eq!(from_token("B5Y="), Err(TokenError::ValueBeyondSize));
+    }
+
+    #[test]
+    fn check_lines_skips_comments_and_reports_malformed_lines() {
+        let input = b"# a comment\n\n0110100\n01x\n";
+        let mut output = vec![];
+
+        let all_correct = check_lines(&mut &input[..], &mut output).unwrap();
+
+        assert!(! all_correct);
+        assert_eq!(String::from_utf8(output).unwrap(),
+            format!("0110100 : correct\nline 4 : {}\n", Configuration::<u32>::from_str("01x").unwrap_err()));
+    }
+
+    #[test]
+    fn uniform_configurations_are_fixed_points() {
+        for size in 2..=12 {
+            assert_eq!(check_uniform_fixed_points(size), None, "size {size}");
+        }
+        assert!(! Configuration::new(0b01u32, 2).is_fixed_point());
+    }
+
+    #[test]
+    fn invariants_hold() {
+        let mut output = vec![];
+        assert!(check_invariants(&mut output).unwrap(), "{}", String::from_utf8_lossy(&output));
+    }
+
+    #[test]
+    fn taken_count_follows_the_traversals() {
+        let mut x = Configuration::new(0b0010110u32, 7);
+        assert_eq!(x.taken_count(), 0);
+        x.update();
+        // 0X1X100 : the first head took the cells 1 and 3
+        assert_eq!(x.taken_count(), 2);
+        x.update();
+        assert_eq!(x.taken_count(), 4);
+        assert_eq!(x.progress_fraction(), 4.0 / 7.0);
+
+        // bits beyond the size are not counted
+        x.taken |= 1 << 9;
+        assert_eq!(x.taken_count(), 4);
+    }
+
+    #[test]
+    fn uniform_values_can_come_before_convergence() {
+        let steps = |value : u32| {
+            let (converged, remaining) = Configuration::new(value, 7).run_with_budget(20);
+            assert!(converged);
+            20 - remaining
+        };
+
+        assert_eq!(Configuration::new(0b0010110u32, 7).first_uniform_value_step(), Some(5));
+        assert_eq!(steps(0b0010110), 6);
+        assert_eq!(Configuration::new(0b0000001u32, 7).first_uniform_value_step(), Some(3));
+        assert_eq!(steps(0b0000001), 3);
+        assert_eq!(Configuration::new(0b1111111u32, 7).first_uniform_value_step(), Some(0));
+    }
+
+    /**
+     * A rule which never converges : it clears the lowest 1 of the value
+     * until a single one is left, and then rotates it forever.
+     */
+    struct DrainThenRotate;
+
+    impl Rule for DrainThenRotate {
+        const NAME : &'static str = "drain then rotate";
+
+        fn update(x : &mut Configuration) {
+            if x.value.count_ones() > 1 {
+                x.value &= x.value - 1;
+            }
+            else {
+                x.value = (x.value << 1 | x.value >> (x.size - 1)) & ((1 << x.size) - 1);
+            }
+        }
+
+        fn has_converged(_ : &Configuration) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn cycles_are_found_by_both_methods() {
+        for method in [CycleDetection::StoreStates, CycleDetection::Brent] {
+            let cycle = |value : u32| Configuration::new(value, 5).detect_cycle_with::<DrainThenRotate>(100, method);
+
+            assert_eq!(cycle(0b00001), CycleInfo { tail : 0, period : 5 }, "{method:?}");
+            assert_eq!(cycle(0b00111), CycleInfo { tail : 2, period : 5 }, "{method:?}");
+            assert_eq!(cycle(0b00000), CycleInfo { tail : 0, period : 1 }, "{method:?}");
+            // the cap is reached before the cycle closes
+            assert_eq!(Configuration::new(0b00111u32, 5).detect_cycle_with::<DrainThenRotate>(4, method),
+                CycleInfo { tail : 4, period : 0 }, "{method:?}");
+        }
+
+        // the rule itself converges
+        assert_eq!(Configuration::new(0b0010110u32, 7).detect_cycle(100), CycleInfo { tail : 6, period : 0 });
+        assert_eq!(Configuration::new(0b0010110u32, 7).detect_cycle(3), CycleInfo { tail : 3, period : 0 });
+    }
+
+    #[test]
+    #[should_panic(expected = "configuration of size 40 is too large, the maximum is 31")]
+    fn over_wide_configurations_are_rejected() {
+        Configuration::new(1u32, 40);
+    }
+
+    #[test]
+    fn over_wide_strings_are_rejected() {
+        let cells = "01".repeat(20);
+        assert_eq!(Configuration::<u32>::from_str(&cells), Err(ParseConfigurationError::TooLong(40)));
+        assert_eq!(Configuration::<u32>::from_str(&cells[..31]).map(|x| x.size), Ok(31));
+        assert_eq!(Configuration::<u64>::new(1, 40).size, 40);
+    }
+
+    /**
+     * Checks the accounting of run_with_budget on all the configurations of a
+     * given size : with the convergence bound as budget, the unused budget is
+     * what the execution did not need, and splitting the budget in two runs
+     * ends in the same state with the same total left. Returns the value of
+     * the first configuration for which it does not hold, if any.
+     */
+    fn check_budget_accounting(size : u32) -> Option<u32> {
+        let bound = expected_convergence_bound(size);
+
+        (0..1 << size).find(|&value| {
+            let mut steps = 0;
+            Configuration::new(value, size).for_each_step(|_| steps += 1);
+            let steps = steps - 1; // the initial state is not an update
+
+            let mut whole = Configuration::new(value, size);
+            let (converged, remaining) = whole.run_with_budget(bound);
+
+            let mut split = Configuration::new(value, size);
+            let (_, first) = split.run_with_budget(1);
+            let (_, second) = split.run_with_budget(bound - 1);
+
+            ! converged || remaining != bound - steps || split != whole || first + second != remaining
+        })
+    }
+
+    #[test]
+    fn budgets_return_what_they_did_not_use() {
+        // 0110100 converges after 6 updates
+        let mut x = Configuration::new(0b0010110u32, 7);
+        assert_eq!(x.run_with_budget(2), (false, 0));
+        assert_eq!(x.run_with_budget(3), (false, 0));
+        assert_eq!(x.run_with_budget(10), (true, 9));
+        // converged configurations keep their whole budget
+        assert_eq!(x.run_with_budget(10), (true, 10));
+        assert_eq!(Configuration::new(0u32, 7).run_with_budget(0), (true, 0));
+
+        for size in 2..=12 {
+            assert_eq!(check_budget_accounting(size), None, "size {size}");
+        }
+    }
+
+    /**
+     * Runs find_taken_drop on every configuration of a given size, and returns
+     * the value of the first one whose taken_count decreases, with the step at
+     * which it does, if any.
+     */
+    fn check_taken_monotone(size : u32) -> Option<(u32, u32)> {
+        (0..1 << size).find_map(|value| {
+            Configuration::new(value, size).find_taken_drop().map(|(step, _)| (value, step))
+        })
+    }
+
+    #[test]
+    fn taken_counts_never_decrease() {
+        for size in 2..=12 {
+            assert_eq!(check_taken_monotone(size), None, "size {size}");
+        }
+        assert_eq!(Configuration::new(0b0010110u32, 7).taken_history(), vec![0, 2, 4, 6, 6, 7, 7]);
+    }
+
+    #[test]
+    fn raw_states_are_validated() {
+        assert_eq!(Configuration::from_raw(0, 0, 0, 0, 0, 0, 0), Err(RawStateError::InvalidSize(0)));
+        assert_eq!(Configuration::from_raw(32, 0, 0, 0, 0, 0, 0), Err(RawStateError::InvalidSize(32)));
+        assert_eq!(Configuration::from_raw(3, 0b1000, 0, 0, 0, 0, 0), Err(RawStateError::BitsBeyondSize("value")));
+        assert_eq!(Configuration::from_raw(3, 0, 0, 0, 0b100, 0, 1 << 5), Err(RawStateError::BitsBeyondSize("mem_1")));
+
+        // 0X1X100, the state of 0110100 after one update
+        let mut x = Configuration::new(0b0010110u32, 7);
+        x.update();
+        let mut y = Configuration::from_raw(7, x.value, x.alphabet, x.taken, x.color, x.mem_0, x.mem_1).unwrap();
+        assert_eq!(y, x);
+        assert!(y.converge());
+        assert_eq!(y.value, 0);
+    }
+
+    #[test]
+    fn raw_states_drive_the_branches_of_the_rule() {
+        // each state packs a left cell as cell 0 and the updated one as cell 1
+        let cases = [
+            // b0- b1-
+            (RuleBranch::Kickstart, (0b10, 0, 0, 0, 0, 0), "i1XB,"),
+            // i0XB; i0XB;
+            (RuleBranch::ColorFlip, (0, 0b11, 0b11, 0, 0b11, 0b11), "i0XR_"),
+            // i0XB, i0XB_
+            (RuleBranch::RevertToOne, (0, 0b11, 0b11, 0, 0, 0b01), "b1X"),
+            // i0XR. b1-
+            (RuleBranch::Scanning, (0b10, 0b01, 0b01, 0b01, 0b01, 0), "i1XR;"),
+        ];
+
+        for (branch, (value, alphabet, taken, color, mem_0, mem_1), after) in cases {
+            let mut x = Configuration::from_raw(2, value, alphabet, taken, color, mem_0, mem_1).unwrap();
+            let left = x.cell(0);
+            assert_eq!(x.rule_branch(0, 1), branch);
+            x.apply_local_function(0, 1);
+            assert_eq!((x.cell(0), x.cell(1)), (left, cell_from_description(after)), "{branch:?}");
+        }
+    }
+
+    /**
+     * Checks, at every step of the executions of all the configurations of a
+     * given size, that intermediate_indices lists the cells which are drawn
+     * with a color by println. Returns the value of the first configuration
+     * for which it does not hold, if any.
+     */
+    fn check_intermediate_indices(size : u32) -> Option<u32> {
+        (0..1 << size).find(|&value| {
+            let mut matches = true;
+            Configuration::new(value, size).for_each_step(|step| {
+                let drawn : Vec<u32> = (0..size).filter(|&k| step.cell_glyphs(k)[1] != ' ').collect();
+                matches &= step.intermediate_indices() == drawn;
+            });
+            ! matches
+        })
+    }
+
+    #[test]
+    fn intermediate_indices_are_the_drawn_cells() {
+        let mut x = Configuration::new(0b0010110u32, 7);
+        assert_eq!(x.intermediate_indices(), vec![]);
+        // 0X1X100 over " BBBBBB" : taken or not, cells 1 to 6 are drawn
+        x.update();
+        assert_eq!(x.intermediate_indices(), vec![1, 2, 3, 4, 5, 6]);
+        // X000000 over "R      "
+        x.run_with_budget(4);
+        assert_eq!(x.intermediate_indices(), vec![0]);
+
+        for size in 2..=10 {
+            assert_eq!(check_intermediate_indices(size), None, "size {size}");
+        }
+    }
+
+    /**
+     * Checks, at every step of the executions of all the configurations of a
+     * given size, that update_with_changes returns the cells whose description
+     * by describe_cell changed. Returns the value of the first configuration
+     * for which it does not hold, if any.
+     */
+    fn check_update_changes(size : u32) -> Option<u32> {
+        (0..1 << size).find(|&value| {
+            let mut x = Configuration::new(value, size);
+
+            while ! x.has_converged() {
+                let before = x;
+                let changes = x.update_with_changes();
+                let diff : Vec<u32> = (0..size)
+                    .filter(|&k| describe_cell(&before, k) != describe_cell(&x, k))
+                    .collect();
+                if changes != diff {
+                    return true;
+                }
+            }
+
+            false
+        })
+    }
+
+    #[test]
+    fn update_with_changes_matches_a_manual_diff() {
+        let mut x = Configuration::new(0b0010110u32, 7);
+        // 0110100 becomes 0X1X100 : the head starts on cell 1 and scans the rest
+        assert_eq!(x.update_with_changes(), vec![1, 2, 3, 4, 5, 6]);
+
+        x.run_with_budget(3);
+        let before = x;
+        let changes = x.update_with_changes();
+        let diff : Vec<u32> = (0..7).filter(|&k| before.cell(k) != x.cell(k)).collect();
+        assert_eq!(changes, diff);
+        // X000000 becomes 0000000
+        assert_eq!(x.update_with_changes(), vec![0]);
+        assert_eq!(x.update_with_changes(), vec![]);
+
+        for size in 2..=10 {
+            assert_eq!(check_update_changes(size), None, "size {size}");
+        }
+    }
+
+    #[test]
+    fn uniform_regions_before_the_first_head_are_preserved() {
+        let initial = Configuration::<u32>::from_str("1110001").unwrap();
+        let mut x = initial;
+        assert_eq!(check_uniform_regions(&mut x), None);
+
+        // the block 111 after the last cell is left alone, and the head
+        // started on cell 3 scans the rest of the ring
+        assert!((0..3).all(|k| x.cell(k) == initial.cell(k)));
+        assert_eq!(x.intermediate_indices(), vec![3, 4, 5, 6]);
+
+        for size in 2..=12 {
+            assert_eq!(check_first_sweep(size), None, "size {size}");
+        }
+    }
+
+    /**
+     * Runs every configuration of a given size until it converges, and
+     * returns the value of the first one whose converged state is not stable
+     * under one more update, if any.
+     */
+    fn check_converged_stable(size : u32) -> Option<u32> {
+        (0..1 << size).find(|&value| {
+            let mut x = Configuration::new(value, size);
+
+            let mut steps = 0;
+            while ! x.has_converged() && steps < expected_convergence_bound(size) {
+                x.update();
+                steps += 1;
+            }
+
+            x.has_converged() && ! x.is_converged_stable()
+        })
+    }
+
+    #[test]
+    fn converged_configurations_are_stable() {
+        let mut x = Configuration::new(0b0010110u32, 7);
+        assert!(! x.is_converged_stable());
+        // X000000 : uniform values, but not converged yet
+        x.run_with_budget(5);
+        assert!(x.has_uniform_value() && ! x.is_converged_stable());
+        x.update();
+        assert!(x.is_converged_stable());
+        assert!(Configuration::new(0b1111111u32, 7).is_converged_stable());
+
+        for size in 2..=12 {
+            assert_eq!(check_converged_stable(size), None, "size {size}");
+        }
+    }
+
+    fn stratified(size : u32, strata : &[(u32, u64, u64)]) -> StratifiedOutcome {
+        let strata = strata.iter()
+            .map(|&(ones, tested, failures)| (ones, SampleOutcome { size, tested, failures, first_failure : None }))
+            .collect();
+        StratifiedOutcome { size, strata }
+    }
+
+    #[test]
+    fn stratified_intervals_use_the_stratified_variance() {
+        let close = |(a, b) : (f64, f64), (c, d) : (f64, f64)| (a - c).abs() < 1e-6 && (b - d).abs() < 1e-6;
+
+        // a variance of 0.002637 for a rate of 0.8125, as a sample of 57.8
+        let outcome = stratified(3, &[(0, 10, 0), (1, 20, 5), (2, 20, 5), (3, 10, 0)]);
+        assert!((outcome.success_rate() - 0.8125).abs() < 1e-12);
+        assert!(close(outcome.confidence_interval(Interval::Wilson), (0.693_633, 0.892_402)));
+        let (low, high) = outcome.confidence_interval(Interval::ClopperPearson);
+        assert!(low < 0.8125 && 0.8125 < high);
+
+        // no failure : a sample of 83.3, on the 10 / 16 of the configurations which are not ties
+        let outcome = stratified(4, &[(0, 5, 0), (1, 40, 0), (3, 40, 0), (4, 5, 0)]);
+        assert!(close(outcome.confidence_interval(Interval::Wilson), (0.972_458, 1.0)));
+
+        let outcome = stratified(3, &[(0, 10, 0), (1, 0, 0), (2, 20, 5), (3, 10, 0)]);
+        assert_eq!(outcome.confidence_interval(Interval::Wilson), (0.0, 1.0));
+    }
+
+    /**
+     * Checks that the Gray code order visits each configuration of the search
+     * of a given size exactly once, and that consecutive configurations differ
+     * by a single cell. Returns the first index at which it does not, if any.
+     */
+    #[cfg(feature = "search")]
+    fn check_gray_code_order(size : u32) -> Option<u32> {
+        let count = 1 << (size - 1);
+        let mut seen = vec![false; count as usize];
+
+        for k in 0..count {
+            let value = gray_code(k);
+            if value >= count || seen[value as usize] {
+                return Some(k);
+            }
+            seen[value as usize] = true;
+
+            if k > 0 && (value ^ gray_code(k - 1)).count_ones() != 1 {
+                return Some(k);
+            }
+        }
+
+        None
+    }
+
+    #[test]
+    #[cfg(feature = "search")]
+    fn gray_code_visits_each_configuration_once() {
+        for size in 2..=16 {
+            assert_eq!(check_gray_code_order(size), None, "size {size}");
+        }
+    }
+
+    /**
+     * The number of orbits of the configurations of sizes 0 to 10, without and
+     * with complement symmetry : the binary necklaces (OEIS A000031) and the
+     * necklaces up to complement (OEIS A000013).
+     */
+    const ORBIT_COUNTS : [(usize, usize); 11] = [
+        (1, 1), (2, 1), (3, 2), (4, 2), (6, 4), (8, 4), (14, 8), (20, 10),
+        (36, 20), (60, 30), (108, 56),
+    ];
+
+    /**
+     * Groups the configurations of a given size into orbits with same_orbit,
+     * and checks that there are as many as listed in ORBIT_COUNTS. Returns
+     * the number of orbits found without and with complement symmetry when
+     * they differ from the known counts.
+     */
+    fn check_orbit_counts(size : u32) -> Option<(usize, usize)> {
+        let count = |with_complement : bool| {
+            let mut representatives : Vec<u32> = vec![];
+            for value in 0..1 << size {
+                if ! representatives.iter().any(|&r| same_orbit(r, value, size, with_complement)) {
+                    representatives.push(value);
+                }
+            }
+            representatives.len()
+        };
+
+        let counts = (count(false), count(true));
+        if counts == ORBIT_COUNTS[size as usize] { None } else { Some(counts) }
+    }
+
+    /**
+     * Checks that all the members of each class of the given size have the
+     * same canonical form, which is itself a member of the class, and that
+     * there are as many canonical forms as classes when they are known.
+     * Returns the value of the first configuration for which it does not
+     * hold, or 0 if only the number of canonical forms is wrong.
+     */
+    fn check_canonical_forms(size : u32) -> Option<u32> {
+        let mut forms = HashSet::new();
+
+        let wrong = (0..1 << size).find(|&value| {
+            let form = canonical_form(value, size);
+            forms.insert(form);
+
+            ! same_orbit(value, form, size, true) ||
+                (0..size).any(|shift| {
+                    let rotated = rotate(value, size, shift);
+                    canonical_form(rotated, size) != form ||
+                        canonical_form(complement(rotated, size), size) != form
+                })
+        });
+
+        wrong.or_else(|| {
+            let expected = ORBIT_COUNTS.get(size as usize).map(|&(_, classes)| classes);
+            expected.filter(|&classes| classes != forms.len()).map(|_| 0)
+        })
+    }
+
+    #[test]
+    fn orbits_and_canonical_forms_match_the_known_counts() {
+        for size in 2..ORBIT_COUNTS.len() as u32 {
+            assert_eq!(check_orbit_counts(size), None, "size {size}");
+        }
+        for size in 2..=12 {
+            assert_eq!(check_canonical_forms(size), None, "size {size}");
+        }
+    }
+
+    /**
+     * Checks that the necklaces of a given size which are canonical forms are
+     * exactly the canonical forms of all the configurations of the size, in
+     * increasing order, and that necklace_count counts them. Returns the
+     * number of canonical forms found among the necklaces when it does not
+     * hold.
+     */
+    fn check_necklaces(size : u32) -> Option<usize> {
+        let forms : Vec<u32> = Necklaces::new(size).filter(|&k| canonical_form(k, size) == k).collect();
+
+        let mut expected : Vec<u32> = (0..1 << size).map(|k| canonical_form(k, size)).collect();
+        expected.sort_unstable();
+        expected.dedup();
+
+        let known = ORBIT_COUNTS.get(size as usize).is_none_or(|&(_, classes)| classes == forms.len());
+        if forms == expected && forms.len() as u64 == necklace_count(size) && known { None } else { Some(forms.len()) }
+    }
+
+    /**
+     * Checks that the necklaces of a given size which are dihedral forms are
+     * exactly the dihedral forms of all the configurations of the size, and
+     * that bracelet_count counts them. Returns the number of dihedral forms
+     * found among the necklaces when it does not hold.
+     */
+    fn check_bracelets(size : u32) -> Option<usize> {
+        let forms : Vec<u32> = Necklaces::new(size).filter(|&k| dihedral_form(k, size) == k).collect();
+
+        let mut expected : Vec<u32> = (0..1 << size).map(|k| dihedral_form(k, size)).collect();
+        expected.sort_unstable();
+        expected.dedup();
+
+        if forms == expected && forms.len() as u64 == bracelet_count(size) { None } else { Some(forms.len()) }
+    }
+
+    #[test]
+    fn necklaces_enumerate_the_canonical_and_dihedral_forms() {
+        for size in 2..=12 {
+            assert_eq!(check_necklaces(size), None, "size {size}");
+            assert_eq!(check_bracelets(size), None, "size {size}");
+        }
+    }
+
+    /**
+     * Returns the first configuration of the given size for which
+     * is_trivially_correct holds but is_correct does not, if any. This is the
+     * sequential version of find_fast_path_mismatch.
+     */
+    fn check_fast_path(size : u32) -> Option<u32> {
+        (0..1 << size).find(|&value| {
+            let mut x = Configuration::new(value, size);
+            x.is_trivially_correct() && ! x.is_correct()
+        })
+    }
+
+    #[test]
+    fn the_fast_path_only_skips_correct_configurations() {
+        for size in 2..=12 {
+            assert_eq!(check_fast_path(size), None, "size {size}");
+        }
+    }
+
+    /**
+     * Checks every configuration of a given size with check_counting, and
+     * returns the value of the first one whose verdict differs from the one
+     * of check, or whose number of updates is not the one found by updating
+     * it until it converges, if any.
+     */
+    fn check_counting(size : u32) -> Option<u32> {
+        let bound = expected_convergence_bound(size);
+
+        (0..1 << size).find(|&k| {
+            let mut x = Configuration::new(k, size);
+            let tie = x.majority(TiePolicy::Undefined).is_none();
+            let mut updates = 0;
+            while ! tie && ! x.has_converged() && updates < bound {
+                x.update();
+                updates += 1;
+            }
+
+            let updates = (! tie).then_some(updates);
+            Configuration::new(k, size).check_counting() != (Configuration::new(k, size).check(), updates)
+        })
+    }
+
+    #[test]
+    fn counted_checks_agree_with_check() {
+        for size in 2..=12 {
+            assert_eq!(check_counting(size), None, "size {size}");
+        }
+    }
+
+    /**
+     * Runs every configuration of a given size until it converges, and
+     * returns the value of the first one whose progress_fraction does not end
+     * at the value documented there, if any.
+     */
+    fn check_final_progress(size : u32) -> Option<u32> {
+        (0..1 << size).find(|&value| {
+            let mut x = Configuration::new(value, size);
+            let minority = value.count_ones().min(size - value.count_ones());
+            let expected = if minority == 0 { 0 } else { (2 * minority + 1).min(size) };
+
+            let mut steps = 0;
+            while ! x.has_converged() && steps < expected_convergence_bound(size) {
+                x.update();
+                steps += 1;
+            }
+
+            x.progress_fraction() != expected as f64 / size as f64
+        })
+    }
+
+    #[test]
+    fn progress_ends_at_the_documented_fraction() {
+        for size in 2..=12 {
+            assert_eq!(check_final_progress(size), None, "size {size}");
+        }
+    }
+
+    /**
+     * Runs every configuration of a given size as a Configuration, a
+     * Configuration64 and a Configuration128, and checks that all their layers
+     * agree at each step up to the convergence bound, and that they get the
+     * same verdict. Returns the value of the first one which does not, if any.
+     */
+    fn check_wide_agreement(size : u32) -> Option<u32> {
+        (0..1 << size).find(|&k| {
+            let mut x = Configuration::new(k, size);
+            let mut x64 = Configuration64::new(k as u64, size);
+            let mut x128 = Configuration128::new(k as u128, size);
+
+            let diverges = (0..=expected_convergence_bound(size)).any(|_| {
+                let differs = x64 != x.widen() || x128 != x.widen();
+                x.update();
+                x64.update();
+                x128.update();
+                differs
+            });
+            let verdict = Configuration::new(k, size).check();
+            diverges || Configuration64::new(k as u64, size).check() != verdict ||
+                Configuration128::new(k as u128, size).check() != verdict
+        })
+    }
+
+    /**
+     * Checks a few configurations of the sizes from 32 to 127, beyond those of
+     * Configuration, whose results are known : a lone 1 and a lone 0 (see
+     * is_trivially_correct), and a block of 1s just below half the size and
+     * its complement, the slowest kind of configuration to converge. The sizes
+     * up to 63 are checked with both Configuration64 and Configuration128.
+     * Returns the first size at which one of them is not correct, if any.
+     */
+    fn check_wide_sizes() -> Option<u32> {
+        (Configuration::<u32>::MAX_SIZE + 1..=Configuration128::MAX_SIZE).find(|&size| {
+            let mask = (1u128 << size) - 1;
+            let block = (1u128 << ((size - 1) / 2)) - 1;
+
+            [1, mask ^ 1, block, mask ^ block].into_iter().any(|value| {
+                let wide_fails = size <= Configuration64::MAX_SIZE &&
+                    ! Configuration64::new(value as u64, size).is_correct();
+                wide_fails || ! Configuration128::new(value, size).is_correct()
+            })
+        })
+    }
+
+    #[test]
+    fn wide_configurations_agree_with_configuration() {
+        for size in 2..=12 {
+            assert_eq!(check_wide_agreement(size), None, "size {size}");
+        }
+        assert_eq!(check_wide_sizes(), None);
+    }
+
+    /**
+     * Checks every configuration of a given size with fixed_check, and
+     * returns the value of the first one whose verdict differs from the one
+     * of Configuration::check, if any.
+     */
+    fn check_fixed_agreement(size : u32) -> Option<u32> {
+        let check = fixed_check(size);
+        (0..1 << size).find(|&k| check(k) != Configuration::new(k, size).check())
+    }
+
+    #[test]
+    fn fixed_configurations_agree_with_check() {
+        for size in 2..=12 {
+            assert_eq!(check_fixed_agreement(size), None, "size {size}");
+        }
+    }
+
+    /**
+     * Runs every configuration of a given size both as a Configuration and as
+     * a LargeConfiguration, and checks that all their cells agree at each step
+     * up to the convergence bound, and that they get the same verdict.
+     * Returns the value of the first one which does not, if any.
+     */
+    fn check_large_agreement(size : u32) -> Option<u32> {
+        (0..1 << size).find(|&k| {
+            let mut x = Configuration::new(k, size);
+            let mut large = LargeConfiguration::from(x);
+
+            let diverges = (0..=expected_convergence_bound(size)).any(|_| {
+                let differs = large != LargeConfiguration::from(x);
+                x.update();
+                large.update();
+                differs
+            });
+            let mut run = Configuration::new(k, size);
+            diverges || LargeConfiguration::from(run).check() != run.check()
+        })
+    }
+
+    /**
+     * Checks LargeConfiguration beyond the sizes of the fixed types : from 32
+     * to 127, the execution of a block of 1s just below half the size must
+     * agree with Configuration128 cell by cell, and on a few sizes of
+     * several hundreds of cells and more, across word boundaries, the
+     * configurations of check_wide_sizes must be correct. Returns the first
+     * size at which this fails, if any.
+     */
+    fn check_large_sizes() -> Option<u32> {
+        let block = |size : u32| -> Vec<bool> { (0..size).map(|k| k < (size - 1) / 2).collect() };
+
+        let disagrees = (Configuration::<u32>::MAX_SIZE + 1..=Configuration128::MAX_SIZE).find(|&size| {
+            let mut x = Configuration128::new((1 << ((size - 1) / 2)) - 1, size);
+            let mut large = LargeConfiguration::from_values(&block(size));
+
+            (0..=expected_convergence_bound(size)).any(|_| {
+                let differs = (0..size).any(|k| x.cell(k) != large.cell(k));
+                x.update();
+                large.update();
+                differs
+            })
+        });
+        if disagrees.is_some() {
+            return disagrees;
+        }
+
+        [128, 129, 255, 256, 1000, 1001].into_iter().find(|&size| {
+            let lone : Vec<bool> = (0..size).map(|k| k == size / 3).collect();
+            let block = block(size);
+            let complement = |values : &[bool]| -> Vec<bool> { values.iter().map(|b| ! b).collect() };
+
+            [complement(&lone), lone, complement(&block), block].iter()
+                .any(|values| ! LargeConfiguration::from_values(values).is_correct())
+        })
+    }
+
+    #[test]
+    fn large_configurations_agree_with_configuration() {
+        for size in 2..=12 {
+            assert_eq!(check_large_agreement(size), None, "size {size}");
+        }
+        assert_eq!(check_large_sizes(), None);
+    }
+
+    /**
+     * Checks the confidence intervals of SampleOutcome against values
+     * computed independently, and returns the number of successes and of
+     * tested configurations of the first one which is off, if any.
+     */
+    fn check_confidence_intervals() -> Option<(u64, u64)> {
+        let known = [
+            // successes, tested, Wilson, Clopper-Pearson
+            (5, 10, (0.236_590, 0.763_410), (0.187_086, 0.812_914)),
+            (10, 10, (0.722_460, 1.0), (0.691_503, 1.0)),
+            (0, 10, (0.0, 0.277_540), (0.0, 0.308_497)),
+            (990, 1000, (0.981_690, 0.994_559), (0.981_687, 0.995_194)),
+            (1000, 1000, (0.996_173, 1.0), (0.996_318, 1.0)),
+        ];
+        let close = |(a, b) : (f64, f64), (c, d) : (f64, f64)| (a - c).abs() < 1e-5 && (b - d).abs() < 1e-5;
+
+        known.into_iter()
+            .find(|&(successes, tested, wilson, clopper_pearson)| {
+                let outcome = SampleOutcome { size : 0, tested, failures : tested - successes, first_failure : None };
+                ! close(outcome.confidence_interval(Interval::Wilson), wilson) ||
+                    ! close(outcome.confidence_interval(Interval::ClopperPearson), clopper_pearson)
+            })
+            .map(|(successes, tested, _, _)| (successes, tested))
+    }
+
+    #[test]
+    fn confidence_intervals_match_known_values() {
+        assert_eq!(check_confidence_intervals(), None);
+    }
+
+    /**
+     * Checks that the shares of density_share add up to 1 over all the
+     * numbers of ones, on the small sizes and on a few large ones, and that
+     * they match the counts of FixedPopcount on the sizes up to 20. Returns
+     * the first size at which this fails, if any.
+     */
+    #[cfg(feature = "search")]
+    fn check_density_shares() -> Option<u32> {
+        (1..=64).chain([200, 1000, 5000]).find(|&size| {
+            let total : f64 = (0..=size).map(|ones| density_share(size, ones)).sum();
+            let counts_differ = size <= 20 && (0..=size).any(|ones| {
+                let count = FixedPopcount::new(size, ones).count() as f64;
+                (density_share(size, ones) * (1u64 << size) as f64 - count).abs() > 1e-6 * count
+            });
+            (total - 1.0).abs() > 1e-9 || counts_differ
+        })
+    }
+
+    #[test]
+    #[cfg(feature = "search")]
+    fn density_shares_add_up_to_one() {
+        assert_eq!(check_density_shares(), None);
+    }
+
+    /**
+     * Checks that the slices of the shards of a few counts follow each other
+     * from 0 to the number of configurations, with sizes differing by at most
+     * one, including when there are more shards than configurations and on
+     * the largest sizes. Returns the first count and number of
+     * configurations for which this fails, if any.
+     */
+    fn check_shards() -> Option<(u64, u64)> {
+        let lengths = [0, 1, 5, 1 << 19, 1 << 29, 1 << 63, u64::MAX];
+        let counts = [1, 2, 3, 7, 1000];
+
+        lengths.iter().flat_map(|&length| counts.iter().map(move |&count| (count, length))).find(|&(count, length)| {
+            let ranges : Vec<Range<u64>> = (1..=count)
+                .map(|index| Shard { index, count, results : PathBuf::new() }.range(length))
+                .collect();
+            let sizes = ranges.iter().map(|range| range.end - range.start);
+
+            ranges[0].start != 0 || ranges[ranges.len() - 1].end != length ||
+            ranges.windows(2).any(|pair| pair[0].end != pair[1].start) ||
+            sizes.clone().max().unwrap_or(0) - sizes.min().unwrap_or(0) > 1
+        })
+    }
+
+    #[test]
+    fn shards_split_the_configurations() {
+        assert_eq!(check_shards(), None);
+    }
+
+    /**
+     * Checks write_merge on the results of the shards of a made up search :
+     * clean when they all are, gaps when one of them is missing or was
+     * interrupted, and counter examples when one of them found one, gaps or
+     * not. Returns the first wrong verdict, if any.
+     */
+    fn check_merge() -> Option<MergeVerdict> {
+        let length = 1000;
+        let results : Vec<ShardResult> = (1..=7).map(|index| ShardResult {
+            size : 11,
+            index,
+            count : 7,
+            order : "complement linear".to_string(),
+            indices : Shard { index, count : 7, results : PathBuf::new() }.range(length),
+            length,
+            tested : 0,
+            result : "clean".to_string(),
+        }).collect();
+
+        let with = |index : usize, result : &str| {
+            let mut results = results.clone();
+            results[index].result = result.to_string();
+            results
+        };
+        let cases = [
+            (results.clone(), MergeVerdict::Clean),
+            (results[1..].to_vec(), MergeVerdict::Gaps),
+            (with(3, "interrupted"), MergeVerdict::Gaps),
+            (with(6, "counter example 0x2A"), MergeVerdict::CounterExamples),
+        ];
+
+        cases.into_iter().find_map(|(results, expected)| {
+            let verdict = write_merge(&results, &mut io::sink()).expect("writing to a sink cannot fail");
+            (verdict != expected).then_some(verdict)
+        })
+    }
+
+    #[test]
+    fn merges_of_shards_give_the_expected_verdicts() {
+        assert_eq!(check_merge(), None);
+    }
+}
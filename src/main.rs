@@ -1,419 +1,768 @@
-#![allow(dead_code)]
-
 /*
  * written by Pacôme Perrotin
  */
 
-use indicatif::{ParallelProgressIterator, ProgressStyle};
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
-use std::iter::Iterator;
+/*
+ * The command line of the density checker, around the density_checker
+ * library which does the actual work.
+ *
+ * Install rust and run "cargo run --release -- help" to list the
+ * commands; without a command, all the sizes are searched.
+ */
 
+use clap::{Args, Parser, Subcommand};
+use density_checker::*;
 use rand::Rng;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
-/*
- * This single file program computes checks the validity of our
- * sequential solution to the density classification tasks on all configurations
- * up to size 30.
- *
- * Install rust and compile with "cargo run --release" after having
- * uncommented the desired line in the main function's body.
+/**
+ * The exit code when a check fails : a search found a counter example, or
+ * another check found a configuration which does not behave as expected.
+ * The program exits with 0 only when everything it checked is correct.
  */
+const EXIT_FAILURE : i32 = 1;
 
+/**
+ * The exit code when the program could not run : invalid arguments, or a
+ * file which could not be read or written.
+ */
+const EXIT_ERROR : i32 = 2;
 
-fn main() {
+/**
+ * The exit code when a search stopped at its time limit, or a merge of
+ * shards left configurations out, without any counter example : the
+ * result is partial.
+ */
+const EXIT_INCOMPLETE : i32 = 3;
 
-    // To show an execution from a random configuration, uncomment this line.
-    // The parameter controls the size of the initial configuration.
-    // show_random_execution(13);
+/**
+ * The exit code when a search found no counter example, but only tested
+ * one configuration of each class of rotations, see is_reduced_search :
+ * the result is reduced, not exhaustive.
+ */
+const EXIT_REDUCED : i32 = 4;
+
+/**
+ * The exit code when a search was interrupted by SIGINT or SIGTERM, as
+ * for the shells, without any counter example : the result is partial.
+ */
+const EXIT_INTERRUPTED : i32 = 130;
 
-    // To check the solution on all configurations from sizes 2 to 30,
-    // uncomment the following line. Can take a while!
-    search_all();
+/**
+ * The command line of the program. Without a command, all the sizes from
+ * 2 to 30 are searched, as with the search command. The options are
+ * shared by all the commands, and only used by those they make sense for.
+ */
+#[derive(Parser)]
+#[command(about = "Checks a sequential solution to the density classification task", long_about = None,
+    after_help = "Exit codes : 0 when everything checked is correct, 1 when a counter example is found or a check \
+        fails, 2 when the program could not run, 3 when a search stopped at its time limit and 130 when it was \
+        interrupted, without a counter example in both cases, and 4 when a search with --reduce rotation or \
+        reflection is clean, which is not exhaustive.")]
+struct Cli {
+    #[command(subcommand)]
+    command : Option<Command>,
+    #[command(flatten)]
+    options : CliOptions,
 }
 
 /**
- * This struct encodes the state of a configuration of sizes up to 31.
- * To allow for the best performances, we do not use any array types,
- * and instead encode the information in 32 bits numbers.
- * One number is used for each property we would like to keep track of.
- * This results in a very fast execution, even for configuration of size 30,
- * because most of the program's memory is likely to fit in a CPU cache.
- *
- * While the Configuration struct could theoretically be more compact
- * (when the "taken" flag is 1, the "value" flag becomes useless)
- * it would be at the detriment of speed.
- *
- * We always assume all intermediate values (all except size and value)
- * are at 0 at the start of an execution, otherwise the program would
- * lead to undefined behavior.
+ * The options of the command line, from which the PrintOptions and the
+ * SearchOptions are built.
  */
-#[derive(Default)]
-pub struct Configuration {
-    // How many bits do we use on each following number?
-    pub size : u32,
-    // Is the value a 0 or a 1?
-    pub value : u32,
-    // Is the current symbol from the intermediate alphabet?
-    pub alphabet : u32,
-    // Has the symbol been removed using an X?
-    pub taken : u32,
-    // Is the local counter odd or even?
-    pub color : u32,
-    // Does the local memory contain a 0?
-    pub mem_0 : u32,
-    // Does the local memory contain a 1?
-    pub mem_1 : u32,
+#[derive(Args)]
+struct CliOptions {
+    #[arg(long, global = true, help = "Print a ruler with the index of each cell above executions")]
+    ruler : bool,
+    #[arg(long, global = true, help = "Print time from left to right instead of from top to bottom")]
+    transpose : bool,
+    #[arg(long, global = true, help = "Search the configurations in a pseudo-random order")]
+    shuffle : bool,
+    #[arg(long, global = true, help = "Seed of the shuffled order, of fuzz and of show, random if not given")]
+    seed : Option<u64>,
+    #[arg(long, global = true, help = "File the counter examples found are appended to")]
+    corpus : Option<PathBuf>,
+    #[arg(long, global = true, help = "Return the smallest counter example of each size")]
+    deterministic : bool,
+    #[arg(long, global = true, help = "Search the configurations in the order of the Gray code")]
+    gray : bool,
+    #[arg(long, global = true, help = "Directory the diagrams of counter examples are saved to")]
+    dump_images : Option<PathBuf>,
+    #[arg(long, global = true, help = "Skip the simulation of configurations with at most one minority cell")]
+    fast_extremes : bool,
+    #[arg(long, global = true, help = "Convergence map whose clean configurations are skipped")]
+    known_clean : Option<PathBuf>,
+    #[arg(long, global = true, value_name = "SECONDS", help = "Stop the searches after this many seconds")]
+    time_limit : Option<u64>,
+    #[arg(long, global = true, help = "Run show and fuzz on the configurations of unlimited size, whatever the size")]
+    large : bool,
+    #[arg(long, global = true, value_parser = parse_reduction, default_value = "complement",
+        help = "Configurations the searches test : full for all of them, complement for half of them, \
+            rotation for one per class of rotations, or reflection for one per class of rotations and mirror images")]
+    reduce : Reduction,
+    #[arg(long, global = true, help = "File the progress of the searches is saved to")]
+    checkpoint : Option<PathBuf>,
+    #[arg(long, global = true, requires = "checkpoint", help = "Resume the searches where the checkpoint says they stopped")]
+    resume : bool,
+    #[arg(long, global = true, value_name = "I/N", value_parser = parse_shard,
+        help = "Only search the I-th of N disjoint slices of each size, from 1/N to N/N")]
+    shard : Option<(u64, u64)>,
+    #[arg(long, global = true, value_name = "DIRECTORY", default_value = ".",
+        help = "Directory the result of each size searched with --shard is written to")]
+    shard_results : PathBuf,
+    #[arg(long, global = true, value_parser = parse_output, default_value = "text",
+        help = "How the searches report their results : text, or json for one JSON object per size")]
+    output : OutputFormat,
+    #[arg(long, global = true, value_name = "PATH", help = "CSV file a row of statistics is appended to for each size searched")]
+    stats_csv : Option<PathBuf>,
+    #[arg(long, global = true, value_name = "PATH", help = "JSONL log the result of each size searched is appended to")]
+    results_log : Option<PathBuf>,
+    #[arg(long, global = true, value_name = "PATH", help = "SQLite database the searches are recorded in, through the sqlite3 command line shell which has to be installed")]
+    db : Option<PathBuf>,
 }
 
 /**
- * A helper function which copies a flag from another in a u32 number.
- * Inlined for better performances.
+ * The commands of the command line. Those checking something exit with a
+ * status of 1 when the check fails, and all exit with a status of 2 on
+ * invalid arguments or output errors.
  */
-#[inline]
-fn self_assign(mem : &mut u32, to_index : u32, from_index : u32) {
-    if *mem & 1 << from_index != 0 {
-        *mem |= 1 << to_index;
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Search all the sizes from 2 to 30, the default")]
+    Search,
+    #[command(about = "Search all the configurations of a size")]
+    Verify {
+        #[arg(long, value_parser = size_parser(2, Configuration64::MAX_SIZE))]
+        size : u32,
+    },
+    #[command(about = "Show the execution of a configuration, random unless a value is given")]
+    Show {
+        #[arg(long, value_parser = size_parser(1, u32::MAX))]
+        size : u32,
+        #[arg(long, value_parser = parse_value, help = "Value of the configuration, in decimal or 0x hexadecimal")]
+        value : Option<u128>,
+    },
+    #[command(about = "Search the sizes in increasing order up to the first one with a counter example")]
+    FirstFailingSize {
+        #[arg(long, value_parser = size_parser(2, Configuration64::MAX_SIZE))]
+        max : u32,
+    },
+    #[command(about = "Merge the result files of sharded searches, and check that they cover every size")]
+    Merge {
+        #[arg(required = true, help = "Result files, or directories of result files")]
+        paths : Vec<PathBuf>,
+    },
+    #[command(about = "Check a corpus of counter examples again")]
+    Reverify {
+        #[arg(default_value = "corpus.txt")]
+        path : String,
+    },
+    #[command(about = "Search the configurations of a size with a given number of 1s")]
+    Density {
+        #[arg(long, value_parser = size_parser(2, Configuration::<u32>::MAX_SIZE))]
+        size : u32,
+        #[arg(long)]
+        ones : u32,
+    },
+    #[command(about = "Count the counter examples of a size by density")]
+    DensityHistogram {
+        #[arg(long, value_parser = size_parser(2, Configuration::<u32>::MAX_SIZE))]
+        size : u32,
+    },
+    #[command(about = "Histograms of the steps and traversals of the executions of a size")]
+    Traversals {
+        #[arg(long, value_parser = size_parser(2, Configuration::<u32>::MAX_SIZE))]
+        size : u32,
+    },
+    #[command(about = "Find the single cell flips which change the result by more than expected")]
+    Sensitivity {
+        #[arg(long, value_parser = size_parser(2, Configuration::<u32>::MAX_SIZE))]
+        size : u32,
+    },
+    #[command(about = "Write the convergence map of a size to a file")]
+    ExportMap {
+        #[arg(long, value_parser = size_parser(2, Configuration::<u32>::MAX_SIZE))]
+        size : u32,
+        // a positional argument, --output being the format of the searches
+        #[arg(value_name = "OUTPUT")]
+        path : PathBuf,
+    },
+    #[command(about = "Compare two convergence maps, the second one being computed if not given")]
+    DiffTables {
+        baseline : String,
+        current : Option<String>,
+    },
+    #[command(about = "Check the predicted results against the executions of a size")]
+    PredictCheck {
+        #[arg(long, value_parser = size_parser(2, Configuration::<u32>::MAX_SIZE))]
+        size : u32,
+    },
+    #[command(about = "Check that the first sweep preserves the uniform regions")]
+    Regions {
+        #[arg(long, value_parser = size_parser(2, Configuration::<u32>::MAX_SIZE))]
+        size : u32,
+    },
+    #[command(about = "Check the fast path for density extremes against full simulation")]
+    FastPathCheck {
+        #[arg(long, value_parser = size_parser(2, Configuration::<u32>::MAX_SIZE))]
+        size : u32,
+    },
+    #[command(about = "Checksum of the executions of all the configurations of a size")]
+    TraceChecksum {
+        #[arg(long, value_parser = size_parser(2, Configuration::<u32>::MAX_SIZE))]
+        size : u32,
+    },
+    #[command(about = "Check that the ties of a size converge to a given value")]
+    Ties {
+        #[arg(long, value_parser = size_parser(2, Configuration::<u32>::MAX_SIZE))]
+        size : u32,
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=1))]
+        convention : u8,
+    },
+    #[command(about = "Print the transition table of the local function")]
+    Transitions,
+    #[command(about = "Check that the rule is symmetric between 0 and 1, which the searches rely on")]
+    ComplementSymmetry,
+    #[command(about = "Check random configurations of a size")]
+    Fuzz {
+        #[arg(long, value_parser = size_parser(1, u32::MAX))]
+        size : u32,
+        #[arg(long)]
+        iters : u64,
+    },
+    #[command(about = "Estimate the share of correct configurations of the sizes too large to be searched")]
+    Sample {
+        #[arg(long, value_parser = size_parser(1, u32::MAX), default_value = "31")]
+        min : u32,
+        #[arg(long, value_parser = size_parser(1, u32::MAX), default_value = "200")]
+        max : u32,
+        #[arg(long, value_parser = clap::value_parser!(u64).range(1..), default_value = "1000",
+            help = "Number of random configurations tested on each size")]
+        count : u64,
+        #[arg(long, help = "Sample each density on its own, more of them near density 1/2")]
+        stratified : bool,
+        #[arg(long, value_parser = parse_interval, default_value = "wilson",
+            help = "Confidence interval of the success rates : wilson or clopper-pearson")]
+        interval : Interval,
+    },
+    #[command(about = "Measure the throughput of the checks on a few sizes")]
+    Calibrate,
+    #[command(about = "Compare the speed of the search orders on a size")]
+    OrderBench {
+        #[arg(long, value_parser = size_parser(2, Configuration::<u32>::MAX_SIZE))]
+        size : u32,
+    },
+    #[command(about = "Check the invariants of the rule on the small sizes")]
+    Invariants,
+    #[command(about = "List the configurations of a size on which the sequential and synchronous rules disagree")]
+    CompareRules {
+        #[arg(long, value_parser = size_parser(2, Configuration::<u32>::MAX_SIZE))]
+        size : u32,
+    },
+    #[command(about = "Check that the rotations of a configuration agree")]
+    Orbit {
+        #[arg(long)]
+        config : Configuration,
+    },
+    #[command(about = "Print the executions of several configurations side by side")]
+    RenderMulti {
+        #[arg(required = true)]
+        configurations : Vec<Configuration>,
+        #[arg(long, default_value = "", allow_hyphen_values = true, help = "Line printed between the executions, \\n for a new line")]
+        separator : String,
+    },
+    #[command(about = "Save the execution of a configuration as a PNG image")]
+    Png {
+        #[arg(long)]
+        config : Configuration,
+        #[arg(value_name = "OUTPUT")]
+        path : PathBuf,
+    },
+    #[command(about = "Print the token of a configuration")]
+    Token {
+        #[arg(long)]
+        config : Configuration,
+    },
+    #[command(about = "Print the configuration of a token")]
+    FromToken {
+        token : String,
+    },
+    #[command(about = "Print the state transition graph of a size in the dot format")]
+    Dot {
+        #[arg(long, value_parser = size_parser(2, Configuration::<u32>::MAX_SIZE))]
+        size : u32,
+    },
+    #[command(about = "Print the execution of a configuration with the case of the rule fired at each cell")]
+    Explain {
+        #[arg(long)]
+        config : Configuration,
+    },
+    #[command(about = "Print the execution of a configuration as one JSON object per line")]
+    Ndjson {
+        #[arg(long)]
+        config : Configuration,
+    },
+    #[command(about = "Print the number of taken cells at each step of an execution")]
+    Taken {
+        #[arg(long)]
+        config : Configuration,
+    },
+    #[command(about = "Check the configurations read from stdin, one per line")]
+    CheckStdin,
+    #[command(about = "Print the key events of the execution of a configuration")]
+    Summary {
+        #[arg(long)]
+        config : Configuration,
+    },
+}
+
+/**
+ * Parses the size of a configuration, from min up to max, the largest
+ * size of the configuration type it is used with.
+ */
+fn size_parser(min : u32, max : u32) -> clap::builder::RangedI64ValueParser<u32> {
+    clap::value_parser!(u32).range(min as i64..=max as i64)
+}
+
+/**
+ * Parses the value of a configuration, in decimal or in hexadecimal with
+ * a 0x prefix, as counter examples are printed.
+ */
+fn parse_value(s : &str) -> Result<u128, std::num::ParseIntError> {
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u128::from_str_radix(hex, 16),
+        None => s.parse(),
     }
-    else {
-        *mem &= !(1 << to_index);
+}
+
+/**
+ * Parses the reduction of the searches, by the name of its symmetry.
+ */
+fn parse_reduction(s : &str) -> Result<Reduction, String> {
+    match s {
+        "full" => Ok(Reduction::Full),
+        "complement" => Ok(Reduction::Complement),
+        "rotation" => Ok(Reduction::Rotation),
+        "reflection" => Ok(Reduction::Reflection),
+        _ => Err(format!("unknown reduction {s}, expected full, complement, rotation or reflection")),
     }
 }
 
 /**
- * A helper function which assigns a boolean value to a specific bit
- * of a u32 number. Inlined for better performances.
+ * Parses a shard, I/N with I from 1 to N.
  */
-#[inline]
-fn assign_bool(to : &mut u32, to_index : u32, value : bool) {
-    if value {
-        *to |= 1 << to_index;
+fn parse_shard(s : &str) -> Result<(u64, u64), String> {
+    let invalid = || format!("invalid shard {s}, expected I/N with I from 1 to N");
+    let (index, count) = s.split_once('/').ok_or_else(invalid)?;
+    let index : u64 = index.parse().map_err(|_| invalid())?;
+    let count : u64 = count.parse().map_err(|_| invalid())?;
+
+    if index == 0 || index > count {
+        return Err(invalid());
     }
-    else {
-        *to &= !(1 << to_index);
+    Ok((index, count))
+}
+
+/**
+ * Parses the output format of the searches.
+ */
+fn parse_output(s : &str) -> Result<OutputFormat, String> {
+    match s {
+        "text" => Ok(OutputFormat::Text),
+        "json" => Ok(OutputFormat::Json),
+        _ => Err(format!("unknown output {s}, expected text or json")),
     }
 }
 
-impl Configuration {
-    /**
-     * Creates a new configuration of a given size and value.
-     * Passing in a value with 1 bits beyond the given size leads to
-     * undefined behavior.
-     */
-    pub fn new(value : u32, size : u32) -> Self {
-        Self {
-            size, value, ..Default::default()
-        }
+/**
+ * Parses the kind of confidence interval of the sampled success rates.
+ */
+fn parse_interval(s : &str) -> Result<Interval, String> {
+    match s {
+        "wilson" => Ok(Interval::Wilson),
+        "clopper-pearson" => Ok(Interval::ClopperPearson),
+        _ => Err(format!("unknown interval {s}, expected wilson or clopper-pearson")),
     }
+}
 
-    /**
-     * Prints the configuration to the screen using three lines,
-     * the first indicates the values of the configuration (or X
-     * if it was taken), the second line indicates the value of
-     * the local counter (R or B), and the third line indicates
-     * the value of the local memory (_ for empty, , for {1}, .
-     * for {0}, and ; for {0, 1}). If a symbol is not from the
-     * intermediary alphabet, its spot on the second and third line
-     * are left blank.
-     */
-    pub fn println(&self) {
-        // first line
-        for k in 0..self.size {
-            if self.alphabet & 1 << k != 0 && self.taken & 1 << k != 0 {
-                print!("X");
+fn main() {
+    let Cli { command, options } = Cli::parse();
+    let mut out = io::stdout();
+
+    let shuffle_seed = options.shuffle.then(|| {
+        let seed = options.seed.unwrap_or_else(|| rand::thread_rng().gen());
+        note(options.output, &format!("shuffling the search order with seed {seed}"), &mut out);
+        seed
+    });
+    let mut search_options = SearchOptions {
+        print : PrintOptions {
+            ruler : options.ruler,
+            transpose : options.transpose,
+        },
+        shuffle_seed,
+        corpus : options.corpus,
+        deterministic : options.deterministic,
+        gray_code : options.gray,
+        dump_images : options.dump_images,
+        fast_extremes : options.fast_extremes,
+        backend : if options.large { Backend::Large } else { Backend::Fixed },
+        reduction : options.reduce,
+        checkpoint : options.checkpoint,
+        resume : None,
+        shard : options.shard.map(|(index, count)| Shard { index, count, results : options.shard_results }),
+        output : options.output,
+        stats_csv : options.stats_csv,
+        results_log : options.results_log,
+        db : None,
+        known_clean : options.known_clean.map(|path| {
+            read_convergence_map(&path).unwrap_or_else(|e| {
+                eprintln!("could not read the known clean map {} : {e}", path.display());
+                std::process::exit(EXIT_ERROR);
+            })
+        }),
+        deadline : options.time_limit.map(|seconds| {
+            std::time::Instant::now() + std::time::Duration::from_secs(seconds)
+        }),
+        progress : Some(progress_bar()),
+    };
+    if search_options.shard.is_some() && matches!(options.reduce, Reduction::Rotation | Reduction::Reflection) {
+        eprintln!("the reductions by rotation cannot be sharded, use --reduce complement or full");
+        std::process::exit(EXIT_ERROR);
+    }
+    if options.resume {
+        search_options.resume = Some(read_checkpoint(&search_options, &mut out));
+    }
+    let seed = || options.seed.unwrap_or_else(|| rand::thread_rng().gen());
+
+    let command = command.unwrap_or(Command::Search);
+    if matches!(command, Command::Search | Command::Verify { .. } | Command::FirstFailingSize { .. }) {
+        // the searches stop cleanly on Ctrl-C, saving their checkpoint
+        install_interrupt_handler();
+    }
+    if let (Some(path), Command::Search | Command::Verify { .. }) = (&options.db, &command) {
+        // only the searches of whole sizes are recorded
+        search_options.db = Some(ResultsDb::open(path, &search_options).unwrap_or_else(|e| {
+            eprintln!("could not open the results database {} : {e}", path.display());
+            std::process::exit(EXIT_ERROR);
+        }));
+    }
+
+    match command {
+        Command::Search => {
+            exit_with_status(exit_on_error(search_all(&search_options, &mut out)));
+        },
+        Command::Verify { size } => {
+            exit_with_status(exit_on_error(search_size(size, &search_options, &mut out)));
+        },
+        Command::Show { size, value } => {
+            let backend = search_options.backend;
+            let result = match value {
+                Some(value) if value.checked_shr(size).is_some_and(|high| high != 0) => {
+                    eprintln!("value {value:#X} does not fit in size {size}");
+                    std::process::exit(EXIT_ERROR);
+                },
+                Some(value) => show_execution(size, value, backend, &search_options.print, &mut out),
+                None => show_random_execution(size, seed(), backend, &search_options.print, &mut out),
+            };
+            exit_on_error(result);
+        },
+        Command::FirstFailingSize { max } => {
+            match first_failing_size(max, &search_options) {
+                Ok(Some((size, value))) => {
+                    exit_on_error(writeln!(out, "smallest failing size : {size}"));
+                    exit_on_error(write_counter_example(size, value, &search_options, &mut out));
+                    std::process::exit(EXIT_FAILURE);
+                },
+                Ok(None) if (2..=max).any(|size| is_reduced_search(size, &search_options)) => {
+                    exit_on_error(writeln!(out, "clean up to {max} (reduced, not exhaustive)"));
+                    std::process::exit(EXIT_REDUCED);
+                },
+                Ok(None) => exit_on_error(writeln!(out, "clean up to {max}")),
+                Err(size) if interrupted() => {
+                    exit_on_error(writeln!(out, "INCOMPLETE : interrupted during size {size}, clean below it"));
+                    std::process::exit(EXIT_INTERRUPTED);
+                },
+                Err(size) => {
+                    exit_on_error(writeln!(out, "INCOMPLETE : time limit reached during size {size}, \
+                        clean below it"));
+                    std::process::exit(EXIT_INCOMPLETE);
+                },
             }
-            else if self.value & 1 << k != 0 {
-                print!("1");
+        },
+        Command::Merge { paths } => {
+            let results = read_shard_results(&paths).unwrap_or_else(|e| {
+                eprintln!("could not read the shard results : {e}");
+                std::process::exit(EXIT_ERROR);
+            });
+            if results.is_empty() {
+                eprintln!("no shard results in {paths:?}");
+                std::process::exit(EXIT_ERROR);
             }
-            else {
-                print!("0");
+            match exit_on_error(write_merge(&results, &mut out)) {
+                MergeVerdict::Clean => (),
+                MergeVerdict::CounterExamples => std::process::exit(EXIT_FAILURE),
+                MergeVerdict::Gaps => std::process::exit(EXIT_INCOMPLETE),
             }
-        }
-        println!();
-
-        // second line
-        for k in 0..self.size {
-            if self.alphabet & 1 << k == 0 {
-                print!(" ");
+        },
+        Command::Reverify { path } => run_reverify(&path, &mut out),
+        Command::Density { size, ones } => {
+            match find_counter_example_at_density(size, ones) {
+                Ok(Some(value)) => {
+                    exit_on_error(writeln!(out, "counter example at density {ones}/{size} : {value:#X}"));
+                    std::process::exit(EXIT_FAILURE);
+                },
+                Ok(None) => exit_on_error(writeln!(out, "size {size} with {ones} ones clean")),
+                Err(DensityError::Tie) => {
+                    exit_on_error(writeln!(out, "size {size} with {ones} ones skipped : {}", DensityError::Tie));
+                },
+                Err(e) => {
+                    eprintln!("{e}");
+                    std::process::exit(EXIT_ERROR);
+                },
             }
-            else if self.color & 1 << k != 0 {
-                print!("R");
+        },
+        Command::DensityHistogram { size } => {
+            if ! exit_on_error(write_failure_histogram(size, &mut out)) {
+                std::process::exit(EXIT_FAILURE);
             }
-            else {
-                print!("B");
+        },
+        Command::Traversals { size } => exit_on_error(write_traversal_histogram(size, &mut out)),
+        Command::Sensitivity { size } => {
+            let anomalies = find_sensitivity_anomalies(size);
+            exit_on_error(writeln!(out, "{} sensitivity anomalies at size {size}", anomalies.len()));
+            for (value, k) in anomalies.iter().take(10) {
+                exit_on_error(writeln!(out, "{value:#X} flipped at {k}"));
             }
-        }
-        println!();
-
-        // third line
-        for k in 0..self.size {
-            if self.alphabet & 1 << k == 0 {
-                print!(" ");
+            if ! anomalies.is_empty() {
+                std::process::exit(EXIT_FAILURE);
             }
-            else {
-                match (self.mem_0 & 1 << k != 0, self.mem_1 & 1 << k != 0) {
-                    (false, false) => print!("_"),
-                    (true, false) => print!("."),
-                    (false, true) => print!(","),
-                    (true, true) => print!(";"),
-                }
+        },
+        Command::ExportMap { size, path } => {
+            exit_on_file_error(write_convergence_map(size, &path), "write the convergence map", &path)
+        },
+        Command::DiffTables { baseline, current } => {
+            // the current map is computed when only the baseline is given
+            let baseline = exit_on_file_error(read_convergence_map(&baseline), "read the convergence map", &baseline);
+            let current = match current {
+                Some(path) => exit_on_file_error(read_convergence_map(&path), "read the convergence map", &path),
+                None => convergence_map(baseline.size),
+            };
+            if baseline.size != current.size {
+                eprintln!("cannot compare maps of sizes {} and {}", baseline.size, current.size);
+                std::process::exit(EXIT_ERROR);
             }
-        }
-        
-        println!();
-    }
-
-    /**
-     * The function which does the real work and applies the automata's
-     * local function at a given index. As our local rule is
-     * sequential, only one index is updated.
-     * The left parameter is used to indicate which index is at the left
-     * of the current value; this value depends on the size of the configuration
-     * and passing it this way saves a step of computation.
-     */
-    #[inline]
-    pub fn apply_local_function(&mut self, left : u32, index : u32) {
-        let left_mask = 1 << left;
-        let index_mask = 1 << index;
-
-        // if left is boolean
-        if self.alphabet & left_mask == 0 {
-            // if we are boolean
-            if self.alphabet & index_mask == 0 {
-                // 00 -> 0, 11 -> 1
-                if (self.value & left_mask == 0) == (self.value & index_mask == 0) {
-                    return;
-                }
-
-                // 01 or 10, kick start 
-                self.alphabet |= index_mask; // we are now intermediate
-                if self.value & index_mask != 0 { // we put the character in memory
-                    self.mem_1 |= index_mask;
-                }
-                else {
-                    self.mem_0 |= index_mask;
-                }
-                self.taken |= index_mask; // and remove the character
-
-                return;
+            if ! exit_on_error(write_map_diff(&baseline, &current, &mut out)) {
+                std::process::exit(EXIT_FAILURE);
             }
-
-            // if we are not boolean, propagation
-            self.alphabet &= ! index_mask; // we are now boolean
-            self_assign(&mut self.value, index, left); // we copy the value from left
-
-            return;
-        }
-
-        // left is intermediate
-        
-        // if we are boolean or not the same color
-        if self.alphabet & index_mask == 0 ||
-          (self.color & left_mask == 0) != (self.color & index_mask == 0) {
-            // we are scanning, we propagate the color and update the memory
-            
-            self.alphabet |= index_mask; // we ensure we are intermediate
-            self_assign(&mut self.color, index, left); // we copy the color
-
-            self_assign(&mut self.mem_0, index, left); // we copy the memory
-            self_assign(&mut self.mem_1, index, left);
-
-            // character already taken, task finished
-            if self.taken & index_mask != 0 {
-                return;
+        },
+        Command::PredictCheck { size } => {
+            match find_prediction_mismatch(size) {
+                Some(value) => {
+                    exit_on_error(writeln!(out, "prediction mismatch at size {size} : {value:#X}"));
+                    std::process::exit(EXIT_FAILURE);
+                },
+                None => exit_on_error(writeln!(out, "size {size} predictions agree")),
             }
-
-            let value = self.value & index_mask != 0;
-            if ! value && self.mem_0 & index_mask != 0 { // value is 0 and we already have one
-                return;
+        },
+        Command::Regions { size } => {
+            match check_first_sweep(size) {
+                Some(value) => {
+                    exit_on_error(writeln!(out, "uniform regions of {value:#X} are not preserved at size {size}"));
+                    std::process::exit(EXIT_FAILURE);
+                },
+                None => exit_on_error(writeln!(out, "size {size} uniform regions preserved")),
             }
-            if value && self.mem_1 & index_mask != 0 { // value is 1 and we already have one
-                return;
+        },
+        Command::FastPathCheck { size } => {
+            match find_fast_path_mismatch(size) {
+                Some(value) => {
+                    exit_on_error(writeln!(out, "fast path mismatch at size {size} : {value:#X}"));
+                    std::process::exit(EXIT_FAILURE);
+                },
+                None => exit_on_error(writeln!(out, "size {size} fast path agrees")),
             }
-
-            self.taken |= index_mask; // we take the character
-
-            if ! value { // and update the memory
-                self.mem_0 |= index_mask;
+        },
+        Command::TraceChecksum { size } => {
+            exit_on_error(writeln!(out, "size {size} : trace checksum {:#018X}", size_trace_checksum(size)));
+        },
+        Command::Ties { size, convention } => {
+            let convention = convention != 0;
+            let (matched, total) = check_ties(size, convention);
+            exit_on_error(writeln!(out, "{matched} of {total} ties converge to {}", convention as u8));
+            if matched != total {
+                std::process::exit(EXIT_FAILURE);
             }
-            else {
-                self.mem_1 |= index_mask;
+        },
+        Command::Transitions => exit_on_error(write_transition_table(&mut out)),
+        Command::ComplementSymmetry => {
+            if ! exit_on_error(write_complement_symmetry(&mut out)) {
+                std::process::exit(EXIT_FAILURE);
             }
-
-            return;
-        }
-
-        // we are the same color, we are the brain of the configuration
-        
-        // if left has a complete set in memory
-        if self.mem_0 & left_mask != 0 && self.mem_1 & left_mask != 0 {
-            let color = self.color & index_mask != 0;
-            assign_bool(&mut self.color, index, ! color); // we invert the color
-            self.mem_0 &= ! index_mask; // we reset the memory
-            self.mem_1 &= ! index_mask;
-
-            // we don't have to try to add the current character, because
-            // it is always taken at the kickstart
-            
-            return;
-        }
-
-        // from here on, all cases are reverting to boolean for convergence
-
-        self.alphabet &= ! index_mask; // we revert to boolean
-
-        // density 1
-        if self.mem_1 & left_mask != 0 {
-            assign_bool(&mut self.value, index, true); // we set value to 1
-            return;
-        }
-        
-        // density 0 or failure
-        assign_bool(&mut self.value, index, false); // we set value to 0
-
-        // we default to all 0 on failure to allow for convergence detection
-    }
-
-    /**
-     * Applies the local function on every index in order.
-     * At this step, we can easily define what the "left" index
-     * is and pass it to the apply_local_function method.
-     */
-    pub fn update(&mut self) {
-        self.apply_local_function(self.size - 1, 0);
-
-        for k in 1..self.size {
-            self.apply_local_function(k - 1, k);
-        }
-    }
-
-    /**
-     * Returns true if the configuration contains no intermediary symbol
-     * and that all the values are either 0 or 1.
-     *
-     * If the value passed to the new function contained 1 bits beyond the
-     * defined size, this function will return false even if all the bits
-     * within the size are equal.
-     */
-    pub fn has_converged(&self) -> bool {
-        self.alphabet == 0 && // no intermediate symbols
-        (self.value == 0 || self.value == (1 << self.size) - 1)
-        // all values are 0 or all values are 1
-    }
-
-    /**
-     * If our local rule fails to compute the correct density value for
-     * the current configuration, this function returns false.
-     * It does it by computing the real density value of the initial
-     * configuration, and then runs the automata to check if the
-     * two values are coherent.
-     * If the initial configuration had as many 1s than 0s (in the case
-     * of an even size), the function always returns true, as our
-     * automata is then not expected to follow any particular behavior,
-     * and is thus correct.
-     */
-    pub fn is_correct(&mut self) -> bool {
-        let mut count_0 = 0;
-        let mut count_1 = 0;
-        for k in 0..self.size {
-            if self.value & 1 << k == 0 { count_0 += 1; }
-            else { count_1 += 1; }
-        }
-
-        if count_0 == count_1 { return true; } // in case of equality, undefined behavior
-
-        let majority = if count_0 > count_1 { 0 }
-            else { 1 };
-
-        let mut iteration_count = 0;
-
-        while ! self.has_converged() {
-
-            if iteration_count > self.size { // We should take around size / 2
-                return false;
+        },
+        Command::Fuzz { size, iters } => {
+            if ! exit_on_error(write_fuzz(size, iters, seed(), &search_options, &mut out)) {
+                std::process::exit(EXIT_FAILURE);
             }
-
-            self.update();
-            iteration_count += 1;
-        }
-
-        majority == self.value & 1 // configuration is uniform, so we only test the first bit
+        },
+        Command::Sample { min, max, count, stratified, interval } => {
+            if min > max {
+                eprintln!("the smallest size {min} is above the largest one {max}");
+                std::process::exit(EXIT_ERROR);
+            }
+            let options = SampleOptions { count, seed : seed(), stratified, interval, backend : search_options.backend };
+            if ! exit_on_error(write_sample(min, max, &options, &mut out)) {
+                std::process::exit(EXIT_FAILURE);
+            }
+        },
+        Command::Calibrate => exit_on_error(calibrate(&mut out)),
+        Command::OrderBench { size } => exit_on_error(write_order_benchmark(size, &mut out)),
+        Command::Invariants => {
+            if ! exit_on_error(check_invariants(&mut out)) {
+                std::process::exit(EXIT_FAILURE);
+            }
+        },
+        Command::CompareRules { size } => {
+            let disagreements = rules_agree::<Sequential, Synchronous>(size);
+            exit_on_error(writeln!(out, "sequential and synchronous rules disagree on {} configurations of size {size}",
+                disagreements.len()));
+            for value in disagreements.iter().take(10) {
+                exit_on_error(writeln!(out, "{value:#X}"));
+            }
+        },
+        Command::Orbit { config } => {
+            if ! exit_on_error(write_orbit(&config, &mut out)) {
+                std::process::exit(EXIT_FAILURE);
+            }
+        },
+        Command::RenderMulti { configurations, separator } => {
+            let configurations : Vec<(u32, u32)> = configurations.iter().map(|x| (x.size, x.value)).collect();
+            let separator = format!("{}\n", separator.replace("\\n", "\n"));
+            exit_on_error(write!(out, "{}", render_multi(&configurations, &separator, &search_options.print)));
+        },
+        Command::Png { mut config, path } => {
+            let mut file = io::BufWriter::new(exit_on_file_error(fs::File::create(&path), "create", &path));
+            let bound = expected_convergence_bound(config.size);
+            exit_on_file_error(write_execution_png(&mut config, bound, &mut file).and_then(|_| file.flush()),
+                "write", &path);
+        },
+        Command::Token { config } => exit_on_error(writeln!(out, "{}", config.to_token())),
+        Command::FromToken { token } => {
+            match Configuration::from_token(&token) {
+                Ok(x) => exit_on_error(writeln!(out, "{}", x.value_string())),
+                Err(e) => {
+                    eprintln!("{e}");
+                    std::process::exit(EXIT_ERROR);
+                },
+            }
+        },
+        Command::Dot { size } => exit_on_error(write!(out, "{}", dot_export(size))),
+        Command::Explain { mut config } => exit_on_error(write_traced_execution(&mut config, &mut out)),
+        Command::Ndjson { mut config } => exit_on_error(write_ndjson(&mut config, &mut out)),
+        Command::Taken { mut config } => exit_on_error(write_taken_history(&mut config, &mut out)),
+        Command::CheckStdin => {
+            if ! exit_on_error(check_lines(&mut io::stdin().lock(), &mut out)) {
+                std::process::exit(EXIT_FAILURE);
+            }
+        },
+        Command::Summary { mut config } => exit_on_error(write_summary(&mut config, &mut out)),
     }
+
+    #[cfg(feature = "profile")]
+    exit_on_error(profile::write_report(&mut out));
 }
 
 /**
- * This function iterates through all the configurations of a given size,
- * and returns any counter-example on which the is_correct method returns
- * false. If no counter example is found, it returns None instead.
- *
- * This function makes uses of parallel iterators for more speed.
+ * Prints a note about how the searches run to the output, or on the standard
+ * error with the json output, so it only holds the reports.
  */
-fn find_counter_example(size : u32) -> Option<u32> {
-    let progress_style =
-        ProgressStyle::with_template("[{eta}] {pos:10}/{len:10} {bar:40}").unwrap();
-
-    (0..1 << size - 1)
-        .into_par_iter()
-        .progress_with_style(progress_style)
-        .map(|k| (k, Configuration::new(k, size).is_correct()) )
-        .filter(|(_, b)| ! b) // we keep the ones that failed
-        .map(|(k, _)| k)
-        .take_any(1)
-        //.take(1)
-        .collect::<Vec<_>>()
-        .iter()
-        .next()
-        .copied() // and return the first one, if there is any
+fn note(output : OutputFormat, message : &str, out : &mut dyn Write) {
+    match output {
+        OutputFormat::Text => exit_on_error(writeln!(out, "{message}")),
+        OutputFormat::Json => eprintln!("{message}"),
+    }
 }
 
 /**
- * Helper function which calls find_counter_example, and if a counter example
- * is found, prints a nice error about it, as well as the execution of
- * the counter example, for inspection by the user.
+ * Reads the checkpoint file of the options to resume from, or exits with
+ * an error message if it cannot be read or was saved by a search in
+ * another order.
  */
-fn search_size(size : u32) {
-    let result = find_counter_example(size);
-
-    if let Some(result) = result {
-        println!("Error in the following example :");
-        let mut x = Configuration::new(result, size);
-        x.println();
-        while ! x.has_converged() {
-            x.update();
-            x.println();
-        }
-    }
-    else {
-        println!("size {size} clean");
+fn read_checkpoint(options : &SearchOptions, out : &mut dyn Write) -> Checkpoint {
+    let path = options.checkpoint.as_ref().expect("resuming needs a checkpoint file");
+    let checkpoint = Checkpoint::read(path).unwrap_or_else(|e| {
+        eprintln!("could not read the checkpoint {} : {e}", path.display());
+        std::process::exit(EXIT_ERROR);
+    });
+
+    let order = search_order(options);
+    if checkpoint.order != order {
+        eprintln!("the checkpoint was saved by a search in the order {:?}, not {order:?} : \
+            give the same options, and the same --seed if shuffling", checkpoint.order);
+        std::process::exit(EXIT_ERROR);
     }
+
+    note(options.output, &format!("resuming at index {} of size {}", checkpoint.next, checkpoint.size), out);
+    checkpoint
 }
 
 /**
- * This function calls search_size for all sizes from 2 to 30, 30 included.
- * Expensive!
+ * Exits with the code of how a search ended, unless it is clean : a
+ * counter example wins over an incomplete search.
  */
-fn search_all() {
-    for size in 2..=30 {
-        search_size(size);
+fn exit_with_status(status : SearchStatus) {
+    match status {
+        SearchStatus::Clean => (),
+        SearchStatus::Reduced => std::process::exit(EXIT_REDUCED),
+        SearchStatus::CounterExample => std::process::exit(EXIT_FAILURE),
+        SearchStatus::Incomplete if interrupted() => std::process::exit(EXIT_INTERRUPTED),
+        SearchStatus::Incomplete => std::process::exit(EXIT_INCOMPLETE),
     }
 }
 
 /**
- * This function generates a random initial configuration of a given size,
- * and prints all the steps of its execution on the terminal until it
- * converges. Useful for generating material to make figures in a scientific
- * article.
+ * Returns the result of an output operation, or exits with an error
+ * message if it failed.
  */
-fn show_random_execution(size : u32) {
-    let mut x = Configuration::new(0, size);
+fn exit_on_error<T>(result : io::Result<T>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("could not write the output : {e}");
+        std::process::exit(EXIT_ERROR);
+    })
+}
 
-    let mut rng = rand::thread_rng();
+/**
+ * Returns the result of an operation on a file other than the output, or
+ * exits with an error message naming the action and the file if it failed.
+ */
+fn exit_on_file_error<T>(result : io::Result<T>, action : &str, path : impl AsRef<Path>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("could not {action} {} : {e}", path.as_ref().display());
+        std::process::exit(EXIT_ERROR);
+    })
+}
 
-    x.value = rng.gen();
-    x.value &= (1 << size) - 1;
+/**
+ * Command line entry point of reverify_corpus. Exits with a non zero status
+ * if the corpus could not be read or if some entry now passes.
+ */
+fn run_reverify(path : &str, out : &mut dyn Write) {
+    let report = match reverify_corpus(path) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("could not read corpus {path} : {e}");
+            std::process::exit(EXIT_ERROR);
+        },
+    };
+
+    exit_on_error(writeln!(out, "{} still failing, {} now passing",
+        report.still_failing.len(), report.now_passing.len()));
+    for (size, value) in &report.now_passing {
+        exit_on_error(writeln!(out, "now passing : size {size} value {value:#X}"));
+    }
 
-    x.println();
-    while ! x.has_converged() {
-        x.update();
-        x.println();
+    if ! report.is_reproduced() {
+        std::process::exit(EXIT_FAILURE);
     }
 }
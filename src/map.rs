@@ -0,0 +1,260 @@
+/*
+ * The convergence maps : the number of updates every configuration of a
+ * size takes to converge, saved to a file so that two versions of the rule
+ * can be compared configuration by configuration.
+ */
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+#[cfg(feature = "search")]
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::Configuration;
+#[cfg(feature = "search")]
+use crate::{Rule, Sequential};
+
+/**
+ * The outcome of every configuration of a given size, stored as bitmaps
+ * of 64 bits words : bit k of the maps (bit k % 64 of word k / 64) is about
+ * Configuration::new(k, size), for all k below 1 << size. A configuration
+ * converges to a 0, converges to a 1, or does not converge, and the three
+ * outcomes are told apart by the values and did_not_converge bitmaps.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConvergenceMap {
+    pub size : u32,
+    // Is the value the configuration converges to a 1? Always 0 for the
+    // configurations which do not converge.
+    pub values : Vec<u64>,
+    // Does the configuration have as many 0s as 1s?
+    pub ties : Vec<u64>,
+    // Did the configuration fail to converge within the cap of
+    // Sequential::converged_value?
+    pub did_not_converge : Vec<u64>,
+}
+
+impl ConvergenceMap {
+    /**
+     * Returns the value configuration k converges to, or None if it does
+     * not converge.
+     */
+    pub fn value(&self, k : u32) -> Option<bool> {
+        if self.did_not_converge[k as usize / 64] & 1 << (k % 64) != 0 {
+            return None;
+        }
+        Some(self.values[k as usize / 64] & 1 << (k % 64) != 0)
+    }
+
+    /**
+     * Returns true if configuration k has as many 0s as 1s.
+     */
+    pub fn is_tie(&self, k : u32) -> bool {
+        self.ties[k as usize / 64] & 1 << (k % 64) != 0
+    }
+
+    /**
+     * Returns true if configuration k was correct when the map was made :
+     * it converged, and it is a tie or it converged to its majority. The
+     * configurations which did not converge are never clean, whatever
+     * their majority.
+     */
+    pub fn is_clean(&self, k : u32) -> bool {
+        self.value(k).is_some_and(|value| self.is_tie(k) || value == (2 * k.count_ones() > self.size))
+    }
+
+    /**
+     * Writes the map in a compact binary format : the magic bytes DCMP,
+     * the format version and the size as little endian u32 numbers, then
+     * the words of the value bitmap, of the tie bitmap and of the did not
+     * converge bitmap as little endian u64 numbers.
+     */
+    pub fn write_to(&self, w : &mut dyn Write) -> io::Result<()> {
+        w.write_all(CONVERGENCE_MAP_MAGIC)?;
+        w.write_all(&CONVERGENCE_MAP_VERSION.to_le_bytes())?;
+        w.write_all(&self.size.to_le_bytes())?;
+        for word in self.values.iter().chain(&self.ties).chain(&self.did_not_converge) {
+            w.write_all(&word.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Reads a map written by write_to. The header is checked, and the
+     * number of words must be exactly the one expected for the size.
+     */
+    pub fn read_from(r : &mut dyn io::Read) -> io::Result<Self> {
+        let invalid = |message : String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let mut header = [0u8; 12];
+        r.read_exact(&mut header)?;
+        if &header[0..4] != CONVERGENCE_MAP_MAGIC {
+            return Err(invalid("not a convergence map".to_string()));
+        }
+        let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        if version != CONVERGENCE_MAP_VERSION {
+            return Err(invalid(format!("unsupported convergence map version {version}")));
+        }
+        let size = u32::from_le_bytes(header[8..12].try_into().unwrap());
+        if size == 0 || size > Configuration::<u32>::MAX_SIZE {
+            return Err(invalid(format!("invalid convergence map size {size}")));
+        }
+
+        let words = (1usize << size).div_ceil(64);
+        let mut bytes = vec![];
+        r.read_to_end(&mut bytes)?;
+        if bytes.len() != 3 * words * 8 {
+            return Err(invalid(format!(
+                "convergence map of size {size} should hold {} bytes of bitmaps, not {}",
+                3 * words * 8, bytes.len())));
+        }
+
+        let mut all = bytes.chunks_exact(8).map(|b| u64::from_le_bytes(b.try_into().unwrap()));
+        let values = all.by_ref().take(words).collect();
+        let ties = all.by_ref().take(words).collect();
+        let did_not_converge = all.collect();
+
+        Ok(ConvergenceMap { size, values, ties, did_not_converge })
+    }
+
+    /**
+     * Returns the configurations whose outcome or tie status is not the
+     * same in the other map, in increasing order, with how they changed
+     * from this map to the other. Both maps must have the same size.
+     */
+    pub fn diff(&self, other : &ConvergenceMap) -> Vec<(u32, MapChange)> {
+        assert_eq!(self.size, other.size, "cannot compare convergence maps of different sizes");
+
+        (0..1u32 << self.size)
+            .filter_map(|k| {
+                let change = match (self.is_tie(k), other.is_tie(k)) {
+                    (true, false) => MapChange::TieToDecided,
+                    (false, true) => MapChange::DecidedToTie,
+                    _ => match (self.value(k), other.value(k)) {
+                        (Some(false), Some(true)) => MapChange::ZeroToOne,
+                        (Some(true), Some(false)) => MapChange::OneToZero,
+                        (Some(_), None) => MapChange::ConvergedToNone,
+                        (None, Some(_)) => MapChange::NoneToConverged,
+                        _ => return None,
+                    },
+                };
+                Some((k, change))
+            })
+            .collect()
+    }
+}
+
+/**
+ * How the entry of a configuration changed between two convergence maps.
+ * A change of tie status takes precedence over a change of outcome.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MapChange {
+    ZeroToOne,
+    OneToZero,
+    // the configuration converged in the first map and not in the second
+    ConvergedToNone,
+    NoneToConverged,
+    TieToDecided,
+    DecidedToTie,
+}
+
+impl MapChange {
+    const ALL : [MapChange; 6] = [Self::ZeroToOne, Self::OneToZero, Self::ConvergedToNone,
+        Self::NoneToConverged, Self::TieToDecided, Self::DecidedToTie];
+
+    /**
+     * A short description of the change, used in the output of diff-tables.
+     */
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::ZeroToOne => "0->1",
+            Self::OneToZero => "1->0",
+            Self::ConvergedToNone => "converged->none",
+            Self::NoneToConverged => "none->converged",
+            Self::TieToDecided => "tie->decided",
+            Self::DecidedToTie => "decided->tie",
+        }
+    }
+}
+
+/**
+ * Writes the configurations whose entry changed between a baseline and a
+ * current convergence map, then how many changed in each way. Returns true
+ * if the maps are the same.
+ */
+pub fn write_map_diff(baseline : &ConvergenceMap, current : &ConvergenceMap, w : &mut dyn Write)
+    -> io::Result<bool> {
+    let diff = baseline.diff(current);
+
+    for &(k, change) in &diff {
+        let x = Configuration::new(k, baseline.size);
+        writeln!(w, "{} {}", x.value_string(), change.label())?;
+    }
+    for change in MapChange::ALL {
+        let count = diff.iter().filter(|(_, c)| *c == change).count();
+        writeln!(w, "{:15} : {count}", change.label())?;
+    }
+
+    Ok(diff.is_empty())
+}
+
+const CONVERGENCE_MAP_MAGIC : &[u8; 4] = b"DCMP";
+const CONVERGENCE_MAP_VERSION : u32 = 2;
+
+/**
+ * Computes the convergence map of a size and writes it to a file.
+ */
+#[cfg(feature = "search")]
+pub fn write_convergence_map(size : u32, path : impl AsRef<Path>) -> io::Result<()> {
+    let mut file = io::BufWriter::new(fs::File::create(path)?);
+    convergence_map(size).write_to(&mut file)?;
+    file.flush()
+}
+
+/**
+ * Reads a convergence map from a file written by write_convergence_map.
+ */
+pub fn read_convergence_map(path : impl AsRef<Path>) -> io::Result<ConvergenceMap> {
+    ConvergenceMap::read_from(&mut io::BufReader::new(fs::File::open(path)?))
+}
+
+/**
+ * Runs every configuration of a given size, in parallel, and returns
+ * their outcomes. Both halves of the configurations are run, so
+ * the map can be compared with any other rule.
+ */
+#[cfg(feature = "search")]
+pub fn convergence_map(size : u32) -> ConvergenceMap {
+    let count = 1u64 << size;
+
+    let words : Vec<(u64, u64, u64)> = (0..count.div_ceil(64))
+        .into_par_iter()
+        .map(|word| {
+            let (mut values, mut ties, mut did_not_converge) = (0u64, 0u64, 0u64);
+            for bit in 0..64.min(count) {
+                let k = (word * 64 + bit) as u32;
+                let mut x = Configuration::new(k, size);
+
+                if 2 * k.count_ones() == size {
+                    ties |= 1 << bit;
+                }
+                match Sequential::converged_value(&mut x) {
+                    Some(true) => values |= 1 << bit,
+                    Some(false) => (),
+                    None => did_not_converge |= 1 << bit,
+                }
+            }
+            (values, ties, did_not_converge)
+        })
+        .collect();
+
+    ConvergenceMap {
+        size,
+        values : words.iter().map(|word| word.0).collect(),
+        ties : words.iter().map(|word| word.1).collect(),
+        did_not_converge : words.iter().map(|word| word.2).collect(),
+    }
+}
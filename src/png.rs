@@ -0,0 +1,89 @@
+/*
+ * A minimal PNG encoder, enough to save space-time diagrams without
+ * pulling in an image crate : 8 bits RGB pixels, no filtering, and the
+ * zlib stream is made of stored (uncompressed) deflate blocks. The files
+ * are larger than they could be, but the diagrams are small anyway.
+ */
+
+use std::io::{self, Write};
+
+/**
+ * Writes an image of the given width and height as a PNG file. The pixels
+ * are given row by row, from the top left corner, as [red, green, blue].
+ */
+pub fn write_png(w : &mut dyn Write, width : u32, height : u32, pixels : &[[u8; 3]]) -> io::Result<()> {
+    assert_eq!(pixels.len(), width as usize * height as usize, "wrong number of pixels");
+
+    w.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+    let mut header = vec![];
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]); // 8 bits RGB, no interlacing
+    write_chunk(w, b"IHDR", &header)?;
+
+    // each row starts with its filter type, 0 for none
+    let mut raw = Vec::with_capacity(height as usize * (1 + 3 * width as usize));
+    for row in pixels.chunks(width.max(1) as usize) {
+        raw.push(0);
+        raw.extend(row.iter().flatten());
+    }
+    write_chunk(w, b"IDAT", &zlib_stored(&raw))?;
+
+    write_chunk(w, b"IEND", &[])
+}
+
+/**
+ * Writes a chunk : its length, its type, its data and the CRC of the type
+ * and the data.
+ */
+fn write_chunk(w : &mut dyn Write, kind : &[u8; 4], data : &[u8]) -> io::Result<()> {
+    w.write_all(&(data.len() as u32).to_be_bytes())?;
+    w.write_all(kind)?;
+    w.write_all(data)?;
+
+    let crc = crc32(kind.iter().chain(data));
+    w.write_all(&crc.to_be_bytes())
+}
+
+/**
+ * Wraps the data in a zlib stream made of stored deflate blocks, each of
+ * at most 65535 bytes, followed by the Adler-32 checksum of the data.
+ */
+fn zlib_stored(data : &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+
+    let blocks : Vec<&[u8]> = if data.is_empty() { vec![&[]] } else { data.chunks(0xFFFF).collect() };
+    for (i, block) in blocks.iter().enumerate() {
+        out.push((i + 1 == blocks.len()) as u8); // is this the last block?
+        let len = block.len() as u16;
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(! len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    out.extend_from_slice(&(b << 16 | a).to_be_bytes());
+
+    out
+}
+
+/**
+ * The CRC-32 used by PNG, computed one bit at a time.
+ */
+fn crc32<'a>(data : impl IntoIterator<Item = &'a u8>) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { 0xEDB8_8320 ^ (crc >> 1) } else { crc >> 1 };
+        }
+    }
+
+    ! crc
+}
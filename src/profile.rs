@@ -0,0 +1,70 @@
+/*
+ * Coarse profiling of the hot path, compiled in with the profile feature
+ * only. The time spent in Configuration::update (the sweeps applying the
+ * local function) is measured against the whole time of
+ * Configuration::check, the rest being the overhead of the loop : density
+ * counting, convergence checks and the measures themselves.
+ *
+ * Each thread sums its own update time, and adds it to the global totals
+ * once per check, so the threads of a search don't fight over the totals.
+ */
+
+use std::cell::Cell;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+static CHECK_NANOS : AtomicU64 = AtomicU64::new(0);
+static UPDATE_NANOS : AtomicU64 = AtomicU64::new(0);
+static CHECKS : AtomicU64 = AtomicU64::new(0);
+static UPDATES : AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    static LOCAL_UPDATE_NANOS : Cell<u64> = const { Cell::new(0) };
+    static LOCAL_UPDATES : Cell<u64> = const { Cell::new(0) };
+}
+
+/**
+ * Runs one update and adds its duration to the current thread's total.
+ */
+#[inline]
+pub fn time_update(update : impl FnOnce()) {
+    let start = Instant::now();
+    update();
+    let nanos = start.elapsed().as_nanos() as u64;
+
+    LOCAL_UPDATE_NANOS.with(|n| n.set(n.get() + nanos));
+    LOCAL_UPDATES.with(|n| n.set(n.get() + 1));
+}
+
+/**
+ * Runs one check, and adds its duration and the duration of the updates
+ * it ran to the global totals.
+ */
+#[inline]
+pub fn time_check<T>(check : impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = check();
+    let nanos = start.elapsed().as_nanos() as u64;
+
+    CHECK_NANOS.fetch_add(nanos, Ordering::Relaxed);
+    CHECKS.fetch_add(1, Ordering::Relaxed);
+    UPDATE_NANOS.fetch_add(LOCAL_UPDATE_NANOS.with(|n| n.replace(0)), Ordering::Relaxed);
+    UPDATES.fetch_add(LOCAL_UPDATES.with(|n| n.replace(0)), Ordering::Relaxed);
+
+    result
+}
+
+/**
+ * Writes a short breakdown of the time spent in checks so far.
+ */
+pub fn write_report(w : &mut dyn Write) -> io::Result<()> {
+    let check_nanos = CHECK_NANOS.load(Ordering::Relaxed);
+    let update_nanos = UPDATE_NANOS.load(Ordering::Relaxed).min(check_nanos);
+    let share = |nanos : u64| if check_nanos == 0 { 0.0 } else { 100.0 * nanos as f64 / check_nanos as f64 };
+
+    writeln!(w, "profile : {} checks, {} updates, {:.3} s in checks",
+        CHECKS.load(Ordering::Relaxed), UPDATES.load(Ordering::Relaxed), check_nanos as f64 * 1e-9)?;
+    writeln!(w, "  local function sweeps {:5.1} %", share(update_nanos))?;
+    writeln!(w, "  overhead              {:5.1} %", share(check_nanos - update_nanos))
+}
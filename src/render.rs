@@ -0,0 +1,370 @@
+/*
+ * The rendering of the executions : as text with their layers, transposed,
+ * traced or as JSON lines, as PNG images, and as a graph of the states of
+ * the rule.
+ */
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+#[cfg(feature = "search")]
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    png, Configuration, Configuration128, Configuration64, EqualityStrategy, LargeConfiguration,
+    Rule, Sequential, StateKey, Word,
+};
+
+/**
+ * Describes the state of one cell of a configuration, see
+ * CellState::describe.
+ */
+pub(crate) fn describe_cell(x : &Configuration, k : u32) -> String {
+    x.cell(k).describe()
+}
+
+/**
+ * Writes the number of taken cells at each step of the execution of
+ * a configuration, as a horizontal bar chart.
+ */
+pub fn write_taken_history(x : &mut Configuration, w : &mut dyn Write) -> io::Result<()> {
+    for (step, count) in x.taken_history().into_iter().enumerate() {
+        writeln!(w, "{step:4} {count:3} {}", "#".repeat(count as usize))?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Writes the summary of the execution of a configuration, one event per
+ * line, with - for the events which did not happen.
+ */
+pub fn write_summary(x : &mut Configuration, w : &mut dyn Write) -> io::Result<()> {
+    let step = |s : Option<u32>| s.map_or("-".to_string(), |s| s.to_string());
+    let summary = x.summarize();
+
+    writeln!(w, "kickstart       : {}", step(summary.kickstart))?;
+    writeln!(w, "first traversal : {}", step(summary.first_traversal))?;
+    writeln!(w, "color flips     : {}", summary.color_flips)?;
+    writeln!(w, "traversals      : {}", summary.traversals)?;
+    writeln!(w, "steps           : {}", summary.steps)?;
+    writeln!(w, "value           : {}", summary.value.map_or("-".to_string(), |v| (v as u8).to_string()))
+}
+
+/**
+ * Writes the execution of a configuration until it converges, with under
+ * each step a fourth line telling which case of the local function fired
+ * on each cell during the update which produced it : K for kickstart,
+ * P for propagation, S for scanning, F for a color flip, 1 and 0 for a
+ * revert to boolean, and . when nothing happened.
+ */
+pub fn write_traced_execution(x : &mut Configuration, w : &mut dyn Write) -> io::Result<()> {
+    x.write(w)?;
+    writeln!(w)?;
+
+    while ! x.has_converged() {
+        let branches = x.update_traced();
+        x.write(w)?;
+        writeln!(w, "{}", branches.iter().map(|b| b.glyph()).collect::<String>())?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Writes the execution of a configuration as newline-delimited JSON : one
+ * line per step, as the execution runs, each line being an object with the
+ * step number and the configuration (see Configuration::to_json). The
+ * writer is flushed after each line so a consumer can follow it live.
+ */
+pub fn write_ndjson(x : &mut Configuration, w : &mut dyn Write) -> io::Result<()> {
+    let mut step = 0;
+
+    x.try_for_each_step(|x| {
+        writeln!(w, "{{\"step\":{step},\"configuration\":{}}}", x.to_json())?;
+        step += 1;
+        w.flush()
+    })
+}
+
+/**
+ * How executions are printed on the terminal.
+ */
+#[derive(Default)]
+pub struct PrintOptions {
+    // Print a ruler with the index of each cell above the execution?
+    pub ruler : bool,
+    // Print time from left to right instead of from top to bottom?
+    pub transpose : bool,
+}
+
+/**
+ * Which configuration type the executions of random or given
+ * configurations run on, as chosen on the command line.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backend {
+    // The fastest type the size fits in : Configuration, Configuration64
+    // or Configuration128, and LargeConfiguration beyond them. Fuzzing
+    // only uses Configuration, and LargeConfiguration beyond it.
+    #[default]
+    Fixed,
+    // LargeConfiguration whatever the size, for instance to compare it
+    // with the fixed types on small sizes.
+    Large,
+}
+
+/**
+ * Writes two lines numbering the cells of a configuration of a given size,
+ * to be aligned above the output of the println method. The first line
+ * gives the tens of every tenth index, and the second one the units of
+ * every index.
+ */
+pub(crate) fn write_ruler(size : u32, w : &mut dyn Write) -> io::Result<()> {
+    for k in 0..size {
+        if k % 10 == 0 && k != 0 {
+            write!(w, "{}", k / 10 % 10)?;
+        }
+        else {
+            write!(w, " ")?;
+        }
+    }
+    writeln!(w)?;
+
+    for k in 0..size {
+        write!(w, "{}", k % 10)?;
+    }
+    writeln!(w)
+}
+
+/**
+ * Writes the execution of a configuration until it converges, following
+ * the given options.
+ */
+pub fn write_execution<W : Word>(x : &mut Configuration<W>, options : &PrintOptions, w : &mut dyn Write)
+    -> io::Result<()> {
+    if options.transpose {
+        return write_transposed_execution(x, options, w);
+    }
+
+    if options.ruler {
+        write_ruler(x.size, w)?;
+    }
+    x.try_for_each_step(|x| x.write(w))
+}
+
+/**
+ * The side, in pixels, of the square drawn for each cell by
+ * write_execution_png.
+ */
+const PNG_CELL_SIZE : u32 = 8;
+
+/**
+ * The color of a cell in the diagrams of write_execution_png : boolean
+ * cells are white for 0 and black for 1, intermediate cells are red or
+ * blue after their color, darker once their character was taken.
+ */
+fn cell_color(x : &Configuration, k : u32) -> [u8; 3] {
+    let mask = 1 << k;
+
+    if x.alphabet & mask == 0 {
+        return if x.value & mask != 0 { [0, 0, 0] } else { [255, 255, 255] };
+    }
+
+    let shade = if x.taken & mask != 0 { 160 } else { 255 };
+    if x.color & mask != 0 { [shade, 64, 64] } else { [64, 64, shade] }
+}
+
+/**
+ * Writes the space-time diagram of the execution of a configuration as a
+ * PNG image : each step is a row of squares, from the initial state at the
+ * top, and each cell a column, colored by cell_color. At most max_updates
+ * updates are drawn, so executions which do not converge can be drawn too.
+ */
+pub fn write_execution_png(x : &mut Configuration, max_updates : u32, w : &mut dyn Write) -> io::Result<()> {
+    let mut steps = vec![*x];
+    while ! x.has_converged() && steps.len() <= max_updates as usize {
+        x.update();
+        steps.push(*x);
+    }
+
+    let width = x.size * PNG_CELL_SIZE;
+    let height = steps.len() as u32 * PNG_CELL_SIZE;
+    let mut pixels = Vec::with_capacity(width as usize * height as usize);
+    for step in &steps {
+        for _ in 0..PNG_CELL_SIZE {
+            for k in 0..x.size {
+                pixels.extend(std::iter::repeat_n(cell_color(step, k), PNG_CELL_SIZE as usize));
+            }
+        }
+    }
+
+    png::write_png(w, width, height, &pixels)
+}
+
+/**
+ * Returns the state-transition graph of the rule on a given size in the
+ * Graphviz DOT format. The nodes are all the states reached from the
+ * initial configurations of that size, labelled with the three lines of
+ * println, and each state has an edge to the state update gives. The
+ * initial configurations are drawn with a double border, and converged
+ * states, the sinks of the graph, have no edge. Converged states only
+ * differ by the taken flags and memories left over in their boolean
+ * cells, which the rule no longer reads, so they are merged into the two
+ * uniform configurations. Nodes are filled after
+ * the value their execution converges to : white for 0, gray for 1, and
+ * red when it does not converge in time.
+ *
+ * The graph grows quickly with the size, so this is meant for sizes up to
+ * 5 or so.
+ */
+pub fn dot_export(size : u32) -> String {
+    let mut ids : HashMap<StateKey, usize> = HashMap::new();
+    let mut states = vec![];
+
+    // the states are numbered in the order they are found
+    let mut pending : Vec<Configuration> = (0..1 << size).map(|k| Configuration::new(k, size)).collect();
+    pending.reverse();
+    let merged = |x : Configuration| {
+        if x.has_converged() { Configuration::new(x.value_code(), size) } else { x }
+    };
+    while let Some(x) = pending.pop() {
+        let x = merged(x);
+        let key = x.state_key(EqualityStrategy::FullState);
+        if ids.contains_key(&key) {
+            continue;
+        }
+        ids.insert(key, states.len());
+        states.push(x);
+
+        if ! x.has_converged() {
+            let mut next = x;
+            next.update();
+            pending.push(next);
+        }
+    }
+
+    let mut dot = String::from("digraph transitions {\n    node [shape=box, style=filled, fontname=monospace];\n");
+    for (id, x) in states.iter().enumerate() {
+        let label = (0..3)
+            .map(|line| (0..size).map(|k| x.cell_glyphs(k)[line]).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\\n");
+        let fill = match Sequential::converged_value(&mut { *x }) {
+            Some(false) => "white",
+            Some(true) => "gray",
+            None => "red",
+        };
+        let initial = if *x == Configuration::new(x.value_code(), size) { ", peripheries=2" } else { "" };
+        dot += &format!("    s{id} [label=\"{label}\", fillcolor={fill}{initial}];\n");
+    }
+    for (id, x) in states.iter().enumerate() {
+        if ! x.has_converged() {
+            let mut next = *x;
+            next.update();
+            dot += &format!("    s{id} -> s{};\n", ids[&merged(next).state_key(EqualityStrategy::FullState)]);
+        }
+    }
+    dot += "}\n";
+
+    dot
+}
+
+/**
+ * Renders the executions of several configurations, given as (size, value)
+ * pairs, one after the other as written by write_execution. Each execution
+ * is preceded by a header naming it, and the separator is put between two
+ * executions. Like write_execution, this never returns if one of the
+ * configurations does not converge.
+ */
+pub fn render_multi(configurations : &[(u32, u32)], separator : &str, options : &PrintOptions) -> String {
+    let mut out = vec![];
+
+    for (i, &(size, value)) in configurations.iter().enumerate() {
+        if i > 0 {
+            out.extend_from_slice(separator.as_bytes());
+        }
+
+        let mut x = Configuration::new(value, size);
+        writeln!(out, "size {size}, value {value:#X} ({})", x.value_string()).unwrap();
+        write_execution(&mut x, options, &mut out).unwrap(); // writing to a Vec never fails
+    }
+
+    String::from_utf8(out).unwrap()
+}
+
+/**
+ * Writes the execution of a configuration with time going from left to
+ * right : each line is a cell, and each step is a column of three
+ * characters (the three lines of println, side by side) followed by a
+ * space. With a ruler, each line starts with the index of its cell.
+ *
+ * Unlike the normal layout, all the steps have to be kept in memory
+ * until the execution is over, since the first line needs all of them.
+ */
+fn write_transposed_execution<W : Word>(x : &mut Configuration<W>, options : &PrintOptions, w : &mut dyn Write)
+    -> io::Result<()> {
+    let mut rows = vec![String::new(); x.size as usize];
+    x.for_each_step(|step| {
+        for (k, row) in rows.iter_mut().enumerate() {
+            row.extend(step.cell_glyphs(k as u32));
+            row.push(' ');
+        }
+    });
+
+    for (k, row) in rows.iter().enumerate() {
+        if options.ruler {
+            write!(w, "{k:3} ")?;
+        }
+        writeln!(w, "{}", row.trim_end())?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Writes all the steps of the execution of the configuration of a given
+ * size and value until it converges, on the configuration type chosen by
+ * the backend. Sizes above 128 can only have values below 1 << 128.
+ */
+pub fn show_execution(size : u32, value : u128, backend : Backend, options : &PrintOptions, w : &mut dyn Write)
+    -> io::Result<()> {
+    if backend == Backend::Large || size > Configuration128::MAX_SIZE {
+        let values : Vec<bool> = (0..size).map(|k| k < u128::BITS && value >> k & 1 != 0).collect();
+        return LargeConfiguration::from_values(&values).write_execution(options, w);
+    }
+    if size > Configuration64::MAX_SIZE {
+        return write_execution(&mut Configuration128::new(value, size), options, w);
+    }
+    if size > Configuration::<u32>::MAX_SIZE {
+        return write_execution(&mut Configuration64::new(value as u64, size), options, w);
+    }
+
+    write_execution(&mut Configuration::new(value as u32, size), options, w)
+}
+
+/**
+ * This function generates a random initial configuration of a given size,
+ * and writes all the steps of its execution until it converges. Useful for generating material to make figures in a scientific
+ * article. The same seed always gives the same configuration.
+ */
+#[cfg(feature = "search")]
+pub fn show_random_execution(size : u32, seed : u64, backend : Backend, options : &PrintOptions, w : &mut dyn Write)
+    -> io::Result<()> {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    if backend == Backend::Large || size > Configuration128::MAX_SIZE {
+        return LargeConfiguration::random(size, &mut rng).write_execution(options, w);
+    }
+    if size > Configuration64::MAX_SIZE {
+        let value = rng.gen::<u128>() & ((1 << size) - 1);
+        return write_execution(&mut Configuration128::new(value, size), options, w);
+    }
+    if size > Configuration::<u32>::MAX_SIZE {
+        let value = rng.gen::<u64>() & ((1 << size) - 1);
+        return write_execution(&mut Configuration64::new(value, size), options, w);
+    }
+
+    let value = rng.gen::<u32>() & ((1 << size) - 1);
+    write_execution(&mut Configuration::new(value, size), options, w)
+}
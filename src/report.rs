@@ -0,0 +1,135 @@
+/*
+ * The records of the searches kept outside of their output : the line of
+ * each run appended to the results log, and the statistics of each size
+ * appended to a CSV file.
+ */
+
+use std::fs;
+use std::io::{self, Write};
+#[cfg(feature = "search")]
+use std::ops::Range;
+use std::path::Path;
+
+use crate::IterationStats;
+#[cfg(feature = "search")]
+use crate::{search_order, SearchOptions};
+
+/**
+ * The header of the statistics files, see SizeReport::to_csv.
+ */
+pub const STATS_CSV_HEADER : &str =
+    "size,tested,total,skipped,counter_examples,max_iterations,mean_iterations,seconds,result";
+
+/**
+ * The report of the search of a size, written as a JSON object with the
+ * json output, see search_size_from.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct SizeReport {
+    pub size : u32,
+    // how many configurations were tested, out of total, and how many of
+    // them were skipped as known clean
+    pub tested : u64,
+    pub total : u64,
+    pub skipped : u64,
+    // the wall time of the search, in seconds
+    pub seconds : f64,
+    // the counter examples found : the search stops at the first one, so
+    // there is at most one
+    pub counter_examples : Vec<u64>,
+    // the updates the executions took, see SearchOutcome
+    pub iterations : Option<IterationStats>,
+    // clean, counter_example, interrupted or time_limit
+    pub result : &'static str,
+}
+
+impl SizeReport {
+    /**
+     * Returns the report as a JSON object, on a single line.
+     */
+    pub fn to_json(&self) -> String {
+        let counter_examples : Vec<String> = self.counter_examples.iter().map(|value| value.to_string()).collect();
+        let (max, mean) = self.iteration_fields("null");
+
+        format!(
+            "{{\"size\":{},\"tested\":{},\"total\":{},\"skipped\":{},\"seconds\":{:.3},\"counter_examples\":[{}],\
+                \"max_iterations\":{max},\"mean_iterations\":{mean},\"result\":\"{}\"}}",
+            self.size, self.tested, self.total, self.skipped, self.seconds, counter_examples.join(","), self.result,
+        )
+    }
+
+    /**
+     * Returns the report as a row of the statistics files, with the
+     * columns of STATS_CSV_HEADER. The counter examples are written in
+     * hexadecimal, separated by spaces.
+     */
+    pub fn to_csv(&self) -> String {
+        let counter_examples : Vec<String> = self.counter_examples.iter().map(|value| format!("{value:#X}")).collect();
+        let (max, mean) = self.iteration_fields("");
+
+        format!("{},{},{},{},{},{max},{mean},{:.3},{}", self.size, self.tested, self.total, self.skipped,
+            counter_examples.join(" "), self.seconds, self.result)
+    }
+
+    /**
+     * Returns the most and the mean updates of the executions, or the
+     * given placeholder for each of them when they were not counted.
+     */
+    fn iteration_fields(&self, missing : &str) -> (String, String) {
+        match self.iterations.as_ref().and_then(|iterations| Some((iterations.max, iterations.mean()?))) {
+            Some((max, mean)) => (max.to_string(), format!("{mean:.3}")),
+            None => (missing.to_string(), missing.to_string()),
+        }
+    }
+}
+
+/**
+ * Returns the number of seconds since the Unix epoch of an instant, as
+ * written in the results logs.
+ */
+pub(crate) fn unix_seconds(time : std::time::SystemTime) -> f64 {
+    time.duration_since(std::time::UNIX_EPOCH).map_or(0.0, |duration| duration.as_secs_f64())
+}
+
+/**
+ * Appends the result of the search of a size to a results log, as one
+ * JSON object on a single line : when the search started and finished,
+ * in seconds since the Unix epoch, the version of the program, the order
+ * of the search and its shard if any, the slice of indices it searched,
+ * from where it was resumed if it was, and the report. The line is
+ * written at once and flushed to the disk, and the log is never
+ * rewritten, so it keeps the history of a campaign across restarts :
+ * only its clean lines prove the slices they cover are clean.
+ */
+#[cfg(feature = "search")]
+pub fn append_results_log(path : impl AsRef<Path>, started : std::time::SystemTime, options : &SearchOptions,
+    indices : Range<u64>, report : &SizeReport) -> io::Result<()> {
+    let counter_examples : Vec<String> = report.counter_examples.iter().map(|value| value.to_string()).collect();
+    let shard = options.shard.as_ref().map_or("null".to_string(), |shard| format!("\"{}/{}\"", shard.index, shard.count));
+    let (max, mean) = report.iteration_fields("null");
+
+    let line = format!(
+        "{{\"started\":{:.3},\"finished\":{:.3},\"version\":\"{}\",\"size\":{},\"order\":\"{}\",\
+            \"shard\":{shard},\"from\":{},\"to\":{},\"tested\":{},\"total\":{},\"skipped\":{},\
+            \"counter_examples\":[{}],\"max_iterations\":{max},\"mean_iterations\":{mean},\"result\":\"{}\"}}\n",
+        unix_seconds(started), unix_seconds(std::time::SystemTime::now()), env!("CARGO_PKG_VERSION"), report.size,
+        search_order(options), indices.start, indices.end, report.tested, report.total,
+        report.skipped, counter_examples.join(","), report.result,
+    );
+
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(line.as_bytes())?;
+    file.sync_all()
+}
+
+/**
+ * Appends the report of a size to a statistics file, which starts with
+ * STATS_CSV_HEADER when it is created.
+ */
+pub fn append_stats_row(path : impl AsRef<Path>, report : &SizeReport) -> io::Result<()> {
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    if file.metadata()?.len() == 0 {
+        writeln!(file, "{STATS_CSV_HEADER}")?;
+    }
+    writeln!(file, "{}", report.to_csv())
+}
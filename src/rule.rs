@@ -0,0 +1,447 @@
+/*
+ * The local rule of the solution : the states of a cell, the cases of the
+ * local function which updates a cell from its left neighbor, written once
+ * for single cells and for the layers of the configurations, and the
+ * tables and fingerprints describing it.
+ */
+
+use std::io::{self, Write};
+
+use crate::{assign_bool, self_assign, Configuration, Word};
+
+/**
+ * The cases of the local function, as labelled by Configuration::rule_branch.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuleBranch {
+    // Both cells are boolean with the same value, nothing happens.
+    Stable,
+    // Both cells are boolean with different values, a head is started.
+    Kickstart,
+    // The left cell is boolean and we are not, we copy its value.
+    Propagation,
+    // The left cell is intermediate and we are boolean or of another
+    // color, we copy its color and memory and maybe take our character.
+    Scanning,
+    // Same color as the left cell, whose memory is complete : a traversal
+    // is over, the color is inverted and the memory reset.
+    ColorFlip,
+    // Same color as the left cell, whose memory only contains 1s : we
+    // revert to a boolean 1.
+    RevertToOne,
+    // Same color as the left cell, whose memory does not contain a 1 : we
+    // revert to a boolean 0, which is also the default on failure.
+    RevertToZero,
+}
+
+impl RuleBranch {
+    /**
+     * A single character standing for the case, used to print the cases
+     * under a configuration.
+     */
+    pub fn glyph(self) -> char {
+        match self {
+            Self::Stable => '.',
+            Self::Kickstart => 'K',
+            Self::Propagation => 'P',
+            Self::Scanning => 'S',
+            Self::ColorFlip => 'F',
+            Self::RevertToOne => '1',
+            Self::RevertToZero => '0',
+        }
+    }
+}
+
+/**
+ * The state of a single cell, as stored across the layers of a
+ * configuration. Used to reason on the local function one neighborhood
+ * at a time without caring about the bit packing, see local_step.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CellState {
+    // false for a boolean cell, true for an intermediate one
+    pub intermediate : bool,
+    pub value : bool,
+    // whether the character of the cell was put in memory
+    pub taken : bool,
+    // R when set, B otherwise
+    pub color : bool,
+    // whether a 0, resp. a 1, is in memory
+    pub mem_0 : bool,
+    pub mem_1 : bool,
+}
+
+impl CellState {
+    /**
+     * Describes the state in five characters : the alphabet (b for
+     * boolean, i for intermediate), the value (0 or 1), whether it was
+     * taken (X) or not (-), the color (R or B, blank for boolean cells)
+     * and the memory (same symbols as the println method, blank for
+     * boolean cells).
+     */
+    pub fn describe(self) -> String {
+        let alphabet = if self.intermediate { 'i' } else { 'b' };
+        let value = if self.value { '1' } else { '0' };
+        let taken = if self.taken { 'X' } else { '-' };
+        let [_, color, memory] = self.glyphs();
+
+        format!("{alphabet}{value}{taken}{color}{memory}")
+    }
+
+    /**
+     * Returns the three characters printed by the println method for the
+     * cell : its value (or X), its local counter and its local memory,
+     * from top to bottom.
+     */
+    pub fn glyphs(self) -> [char; 3] {
+        let value = if self.intermediate && self.taken { 'X' }
+            else if self.value { '1' }
+            else { '0' };
+
+        if ! self.intermediate {
+            return [value, ' ', ' '];
+        }
+
+        let color = if self.color { 'R' } else { 'B' };
+        let memory = match (self.mem_0, self.mem_1) {
+            (false, false) => '_',
+            (true, false) => '.',
+            (false, true) => ',',
+            (true, true) => ';',
+        };
+
+        [value, color, memory]
+    }
+
+    /**
+     * Returns the state with the 0s and 1s swapped : the value, and what
+     * the memory holds. The rule should commute with this, see
+     * complement_asymmetries.
+     */
+    pub fn complement(self) -> CellState {
+        CellState {
+            value : ! self.value,
+            mem_0 : self.mem_1,
+            mem_1 : self.mem_0,
+            ..self
+        }
+    }
+}
+
+/**
+ * Returns which case of local_step fires for a cell and its left
+ * neighbor. The conditions are tested in the same order as in local_step,
+ * which must be kept in sync.
+ */
+pub fn local_branch(left : CellState, current : CellState) -> RuleBranch {
+    if ! left.intermediate {
+        if current.intermediate {
+            return RuleBranch::Propagation;
+        }
+        if left.value == current.value {
+            return RuleBranch::Stable;
+        }
+        return RuleBranch::Kickstart;
+    }
+
+    if ! current.intermediate || left.color != current.color {
+        return RuleBranch::Scanning;
+    }
+
+    if left.mem_0 && left.mem_1 {
+        return RuleBranch::ColorFlip;
+    }
+
+    if left.mem_1 { RuleBranch::RevertToOne }
+    else { RuleBranch::RevertToZero }
+}
+
+/**
+ * The layers of a configuration, which hold one flag of each cell.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Layer {
+    Alphabet,
+    Value,
+    Taken,
+    Color,
+    Mem0,
+    Mem1,
+}
+
+/**
+ * A cell and its left neighbor, as seen by the local function : the
+ * function is written once, in apply_rule, against this trait, and runs
+ * both on explicit cell states (local_step) and right on the bits of the
+ * layers of a configuration (Configuration::apply_local_function).
+ */
+pub(crate) trait Neighborhood {
+    // the flag of the left neighbor on a layer
+    fn left(&self, layer : Layer) -> bool;
+    // the flag of the current cell on a layer
+    fn current(&self, layer : Layer) -> bool;
+    fn set(&mut self, layer : Layer, bit : bool);
+
+    // copies the flag of the left neighbor to the current cell
+    fn copy(&mut self, layer : Layer) {
+        self.set(layer, self.left(layer));
+    }
+}
+
+/**
+ * The local function of the automaton : updates the current cell of a
+ * neighborhood given its state and the one of its left neighbor. The
+ * flags are only read where the case needs them, and the cases return as
+ * soon as they are done, so that the stable cells are not written at all :
+ * on the bits of a configuration, this is as fast as working on the
+ * layers by hand.
+ */
+#[inline(always)]
+pub(crate) fn apply_rule(n : &mut impl Neighborhood) {
+    use Layer::*;
+
+    // if left is boolean
+    if ! n.left(Alphabet) {
+        // if we are boolean
+        if ! n.current(Alphabet) {
+            // 00 -> 0, 11 -> 1
+            if n.left(Value) == n.current(Value) {
+                return;
+            }
+
+            // 01 or 10, kick start
+            n.set(Alphabet, true); // we are now intermediate
+            if n.current(Value) { // we put the character in memory
+                n.set(Mem1, true);
+            }
+            else {
+                n.set(Mem0, true);
+            }
+            n.set(Taken, true); // and remove the character
+
+            return;
+        }
+
+        // if we are not boolean, propagation
+        n.set(Alphabet, false); // we are now boolean
+        n.copy(Value); // we copy the value from left
+
+        return;
+    }
+
+    // left is intermediate
+
+    // if we are boolean or not the same color
+    if ! n.current(Alphabet) || n.left(Color) != n.current(Color) {
+        // we are scanning, we propagate the color and update the memory
+
+        n.set(Alphabet, true); // we ensure we are intermediate
+        n.copy(Color); // we copy the color
+
+        n.copy(Mem0); // we copy the memory
+        n.copy(Mem1);
+
+        // character already taken, task finished
+        if n.current(Taken) {
+            return;
+        }
+
+        let value = n.current(Value);
+        if ! value && n.current(Mem0) { // value is 0 and we already have one
+            return;
+        }
+        if value && n.current(Mem1) { // value is 1 and we already have one
+            return;
+        }
+
+        n.set(Taken, true); // we take the character
+
+        if ! value { // and update the memory
+            n.set(Mem0, true);
+        }
+        else {
+            n.set(Mem1, true);
+        }
+
+        return;
+    }
+
+    // we are the same color, we are the brain of the configuration
+
+    // if left has a complete set in memory
+    if n.left(Mem0) && n.left(Mem1) {
+        n.set(Color, ! n.current(Color)); // we invert the color
+        n.set(Mem0, false); // we reset the memory
+        n.set(Mem1, false);
+
+        // we don't have to try to add the current character, because
+        // it is always taken at the kickstart
+
+        return;
+    }
+
+    // from here on, all cases are reverting to boolean for convergence
+
+    n.set(Alphabet, false); // we revert to boolean
+
+    // density 1 if left only has 1s in memory, otherwise density 0 or
+    // failure : we default to all 0 on failure to allow for convergence
+    // detection
+    n.set(Value, n.left(Mem1));
+}
+
+impl CellState {
+    #[inline(always)]
+    fn flag(self, layer : Layer) -> bool {
+        match layer {
+            Layer::Alphabet => self.intermediate,
+            Layer::Value => self.value,
+            Layer::Taken => self.taken,
+            Layer::Color => self.color,
+            Layer::Mem0 => self.mem_0,
+            Layer::Mem1 => self.mem_1,
+        }
+    }
+
+    #[inline(always)]
+    fn flag_mut(&mut self, layer : Layer) -> &mut bool {
+        match layer {
+            Layer::Alphabet => &mut self.intermediate,
+            Layer::Value => &mut self.value,
+            Layer::Taken => &mut self.taken,
+            Layer::Color => &mut self.color,
+            Layer::Mem0 => &mut self.mem_0,
+            Layer::Mem1 => &mut self.mem_1,
+        }
+    }
+}
+
+/**
+ * A cell and its left neighbor as explicit states, the current one being
+ * updated in place.
+ */
+impl Neighborhood for (CellState, CellState) {
+    fn left(&self, layer : Layer) -> bool {
+        self.0.flag(layer)
+    }
+
+    fn current(&self, layer : Layer) -> bool {
+        self.1.flag(layer)
+    }
+
+    fn set(&mut self, layer : Layer, bit : bool) {
+        *self.1.flag_mut(layer) = bit;
+    }
+}
+
+/**
+ * A cell of a configuration and its left neighbor, given by their indices.
+ */
+pub(crate) struct Cells<'a, W : Word> {
+    pub(crate) configuration : &'a mut Configuration<W>,
+    pub(crate) left : u32,
+    pub(crate) index : u32,
+}
+
+impl<W : Word> Neighborhood for Cells<'_, W> {
+    #[inline(always)]
+    fn left(&self, layer : Layer) -> bool {
+        self.configuration.layer(layer) & W::ONE << self.left != W::ZERO
+    }
+
+    #[inline(always)]
+    fn current(&self, layer : Layer) -> bool {
+        self.configuration.layer(layer) & W::ONE << self.index != W::ZERO
+    }
+
+    #[inline(always)]
+    fn set(&mut self, layer : Layer, bit : bool) {
+        assign_bool(self.configuration.layer_mut(layer), self.index, bit);
+    }
+
+    #[inline(always)]
+    fn copy(&mut self, layer : Layer) {
+        self_assign(self.configuration.layer_mut(layer), self.index, self.left);
+    }
+}
+
+/**
+ * The local function of the automaton : returns the new state of a cell
+ * given its state and the one of its left neighbor.
+ */
+#[inline]
+pub fn local_step(left : CellState, current : CellState) -> CellState {
+    let mut neighborhood = (left, current);
+    apply_rule(&mut neighborhood);
+    neighborhood.1
+}
+
+/**
+ * Builds the state of a cell from six bits, from the lowest : alphabet,
+ * value, taken, color, mem_0 and mem_1.
+ */
+pub(crate) fn local_state(bits : u32) -> CellState {
+    CellState {
+        intermediate : bits & 1 != 0,
+        value : bits >> 1 & 1 != 0,
+        taken : bits >> 2 & 1 != 0,
+        color : bits >> 3 & 1 != 0,
+        mem_0 : bits >> 4 & 1 != 0,
+        mem_1 : bits >> 5 & 1 != 0,
+    }
+}
+
+/**
+ * Writes the effect of local_step on a cell, for every state of the cell
+ * and of its left neighbor. The states are printed with
+ * CellState::describe; rows where the cell is left unchanged are marked with =.
+ *
+ * The rule never reads whether the left cell was taken, nor its value when
+ * it is intermediate, nor its color and memory when it is boolean, so only
+ * the 10 distinguishable left states are listed. The current cell goes
+ * through its 36 reachable states : boolean cells carry no color nor
+ * memory, but may have been taken before reverting to boolean.
+ */
+pub fn write_transition_table(w : &mut dyn Write) -> io::Result<()> {
+    let left_states = (0..64u32)
+        .filter(|b| if b & 1 == 0 { b & !0b10 == 0 } else { b & 0b110 == 0 });
+    let current_states = (0..64u32)
+        .filter(|b| b & 1 != 0 || b & 0b111000 == 0)
+        .collect::<Vec<_>>();
+
+    writeln!(w, "left  cell  -> cell")?;
+    for left in left_states {
+        for &current in &current_states {
+            let (left, current) = (local_state(left), local_state(current));
+            let next = local_step(left, current);
+            let marker = if next == current { '=' } else { ' ' };
+
+            writeln!(w, "{} {} -> {} {marker}", left.describe(), current.describe(), next.describe())?;
+        }
+        writeln!(w)?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Hashes the result of local_step on every state of a cell and of its
+ * left neighbor into a single number, with the FNV-1a of trace_checksum :
+ * two versions of the local function with the same fingerprint almost
+ * certainly are the same function, so results can be tied to the version
+ * of the rule which produced them, see ResultsDb.
+ */
+pub fn rule_fingerprint() -> u64 {
+    let mut hash = 0xCBF2_9CE4_8422_2325u64;
+
+    for left in 0..64 {
+        for current in 0..64 {
+            let next = local_step(local_state(left), local_state(current));
+            let bits = [next.intermediate, next.value, next.taken, next.color, next.mem_0, next.mem_1]
+                .iter().rev().fold(0, |bits, &bit| bits << 1 | bit as u64);
+            hash = (hash ^ bits).wrapping_mul(0x0000_0100_0000_01B3);
+        }
+    }
+
+    hash
+}
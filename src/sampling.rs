@@ -0,0 +1,419 @@
+/*
+ * The checks of random configurations, for the sizes too large to be
+ * searched exhaustively : the fuzzing, and the samples with the confidence
+ * intervals of their success rates, plain or stratified by density.
+ */
+
+#[cfg(feature = "search")]
+use std::io::{self, Write};
+
+#[cfg(feature = "search")]
+use rand::{rngs::StdRng, seq::index, Rng, SeedableRng};
+#[cfg(feature = "search")]
+use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
+
+use crate::{Backend, Interval};
+#[cfg(feature = "search")]
+use crate::{
+    write_counter_example, Configuration, Configuration128, Configuration64, LargeConfiguration,
+    SearchOptions,
+};
+
+/**
+ * Draws `iters` random configurations of the given size from a generator
+ * seeded with `seed`, and checks them in parallel, one chunk at a time,
+ * until one fails. Returns the number of configurations tested, up to and
+ * including the failure, and the failure if there is one. Configurations
+ * are drawn in the same order whatever the scheduling, so a given seed
+ * always gives the same result.
+ */
+#[cfg(feature = "search")]
+pub fn fuzz(size : u32, iters : u64, seed : u64) -> (u64, Option<u32>) {
+    const CHUNK : u64 = 1 << 16;
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mask = ((1u64 << size) - 1) as u32;
+    let mut tested = 0;
+
+    while tested < iters {
+        let chunk : Vec<u32> = (0..CHUNK.min(iters - tested))
+            .map(|_| rng.gen::<u32>() & mask)
+            .collect();
+
+        let failure = chunk.par_iter()
+            .position_first(|&k| ! Configuration::new(k, size).is_correct());
+        if let Some(i) = failure {
+            return (tested + i as u64 + 1, Some(chunk[i]));
+        }
+
+        tested += chunk.len() as u64;
+    }
+
+    (tested, None)
+}
+
+/**
+ * Same as fuzz, on LargeConfiguration, so the size is not limited. The
+ * configurations are much slower to run, so they are checked in smaller
+ * chunks; the failure, if any, is returned in its initial state.
+ */
+#[cfg(feature = "search")]
+pub fn fuzz_large(size : u32, iters : u64, seed : u64) -> (u64, Option<LargeConfiguration>) {
+    const CHUNK : u64 = 1 << 8;
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut tested = 0;
+
+    while tested < iters {
+        let chunk : Vec<LargeConfiguration> = (0..CHUNK.min(iters - tested))
+            .map(|_| LargeConfiguration::random(size, &mut rng))
+            .collect();
+
+        let failure = chunk.par_iter()
+            .position_first(|x| ! x.clone().is_correct());
+        if let Some(i) = failure {
+            return (tested + i as u64 + 1, chunk.into_iter().nth(i));
+        }
+
+        tested += chunk.len() as u64;
+    }
+
+    (tested, None)
+}
+
+/**
+ * Runs fuzz and writes its outcome, with the execution of the failure if
+ * there is one. Returns true if no failure was found.
+ */
+#[cfg(feature = "search")]
+pub fn write_fuzz(size : u32, iters : u64, seed : u64, options : &SearchOptions, w : &mut dyn Write)
+    -> io::Result<bool> {
+    writeln!(w, "fuzzing size {size} with seed {seed}")?;
+
+    if options.backend == Backend::Large || size > Configuration::<u32>::MAX_SIZE {
+        return match fuzz_large(size, iters, seed) {
+            (tested, Some(mut x)) => {
+                writeln!(w, "failure after {tested} configurations : {}", x.value_string())?;
+                writeln!(w, "verdict={:?}", x.clone().check())?;
+                x.write_execution(&options.print, w)?;
+                Ok(false)
+            },
+            (tested, None) => {
+                writeln!(w, "{tested} configurations tested, no failure")?;
+                Ok(true)
+            },
+        };
+    }
+
+    match fuzz(size, iters, seed) {
+        (tested, Some(value)) => {
+            writeln!(w, "failure after {tested} configurations")?;
+            write_counter_example(size, value as u64, options, w)?;
+            Ok(false)
+        },
+        (tested, None) => {
+            writeln!(w, "{tested} configurations tested, no failure")?;
+            Ok(true)
+        },
+    }
+}
+
+/**
+ * The outcome of sample on one size.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SampleOutcome {
+    pub size : u32,
+    // how many configurations were tested, and how many of them failed
+    pub tested : u64,
+    pub failures : u64,
+    // the first failure drawn, if any, as printed by value_string
+    pub first_failure : Option<String>,
+}
+
+impl SampleOutcome {
+    /**
+     * Returns the share of the tested configurations which were correct,
+     * the estimate of the share of all the configurations of the size.
+     */
+    pub fn success_rate(&self) -> f64 {
+        if self.tested == 0 { 1.0 } else { 1.0 - self.failures as f64 / self.tested as f64 }
+    }
+
+    /**
+     * Returns the given kind of interval of the success rate at 95% : the
+     * share of correct configurations among all those of the size is
+     * within it, unless the sample was unlucky.
+     */
+    pub fn confidence_interval(&self, interval : Interval) -> (f64, f64) {
+        interval.bounds((self.tested - self.failures) as f64, self.tested as f64)
+    }
+}
+
+/**
+ * Draws `count` random configurations of the given size from a generator
+ * seeded with `seed`, and checks all of them, in parallel, one chunk at a
+ * time. Unlike fuzz, this does not stop at the first failure, but counts
+ * the failures to estimate the share of correct configurations, which is
+ * what is left to do on the sizes too large to be searched.
+ *
+ * The configurations run on the fastest type the size fits in, up to
+ * Configuration128, and on LargeConfiguration beyond it or with the large
+ * backend. The draws only depend on the seed, so a given seed always
+ * gives the same outcome.
+ */
+#[cfg(feature = "search")]
+pub fn sample(size : u32, count : u64, seed : u64, backend : Backend) -> SampleOutcome {
+    sample_from(size, count, None, backend, &mut StdRng::seed_from_u64(seed))
+}
+
+/**
+ * Same as sample, but draws the configurations from the given generator,
+ * and only those with the given number of ones if there is one.
+ */
+#[cfg(feature = "search")]
+fn sample_from(size : u32, count : u64, ones : Option<u32>, backend : Backend, rng : &mut StdRng) -> SampleOutcome {
+    if backend == Backend::Large || size > Configuration128::MAX_SIZE {
+        return sample_with(size, count, 1 << 8,
+            || match ones {
+                Some(ones) => LargeConfiguration::from_values(&random_values(size, ones, rng)),
+                None => LargeConfiguration::random(size, rng),
+            },
+            |x| x.clone().is_correct(), LargeConfiguration::value_string);
+    }
+
+    let mask = u128::MAX >> (128 - size);
+    sample_with(size, count, 1 << 12,
+        || match ones {
+            Some(ones) => random_values(size, ones, rng).iter().rev().fold(0, |value, &bit| value << 1 | bit as u128),
+            None => rng.gen::<u128>() & mask,
+        },
+        |&value| {
+            if size <= Configuration::<u32>::MAX_SIZE { Configuration::new(value as u32, size).is_correct() }
+            else if size <= Configuration64::MAX_SIZE { Configuration64::new(value as u64, size).is_correct() }
+            else { Configuration128::new(value, size).is_correct() }
+        },
+        |&value| Configuration128::new(value, size).value_string())
+}
+
+/**
+ * The loop of sample : draws the configurations with draw, chunk of them
+ * at a time, and checks each chunk in parallel with is_correct. The first
+ * failure drawn is described with describe.
+ */
+#[cfg(feature = "search")]
+fn sample_with<T : Sync>(size : u32, count : u64, chunk : u64, mut draw : impl FnMut() -> T,
+    is_correct : impl Fn(&T) -> bool + Sync, describe : impl Fn(&T) -> String) -> SampleOutcome {
+    let mut outcome = SampleOutcome { size, tested : 0, failures : 0, first_failure : None };
+
+    while outcome.tested < count {
+        let configurations : Vec<T> = (0..chunk.min(count - outcome.tested)).map(|_| draw()).collect();
+        let failed : Vec<bool> = configurations.par_iter().map(|x| ! is_correct(x)).collect();
+
+        if outcome.first_failure.is_none() {
+            outcome.first_failure = failed.iter().position(|&f| f).map(|i| describe(&configurations[i]));
+        }
+        outcome.failures += failed.iter().filter(|&&f| f).count() as u64;
+        outcome.tested += configurations.len() as u64;
+    }
+
+    outcome
+}
+
+/**
+ * Returns the values of the cells of a random configuration of the given
+ * size with exactly the given number of ones, all such configurations
+ * being equally likely.
+ */
+#[cfg(feature = "search")]
+fn random_values(size : u32, ones : u32, rng : &mut impl Rng) -> Vec<bool> {
+    let mut values = vec![false; size as usize];
+    for k in index::sample(rng, size as usize, ones as usize) {
+        values[k] = true;
+    }
+    values
+}
+
+/**
+ * The outcome of sample_stratified on one size : the outcome of each
+ * stratum, with its number of ones.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StratifiedOutcome {
+    pub size : u32,
+    pub strata : Vec<(u32, SampleOutcome)>,
+}
+
+impl StratifiedOutcome {
+    /**
+     * Returns how many configurations were tested, and how many of them
+     * failed, in all the strata.
+     */
+    pub fn totals(&self) -> (u64, u64) {
+        self.strata.iter().fold((0, 0), |(tested, failures), (_, outcome)| {
+            (tested + outcome.tested, failures + outcome.failures)
+        })
+    }
+
+    /**
+     * Returns the estimate of the share of correct configurations among
+     * all those of the size : the success rate of each stratum, weighted
+     * by the share of the configurations it holds, plus the ties which
+     * are always correct. This corrects for the strata near density 1/2
+     * being sampled more than their share.
+     */
+    pub fn success_rate(&self) -> f64 {
+        self.weighted(|outcome| outcome.success_rate())
+    }
+
+    /**
+     * Returns the given kind of interval of success_rate at 95%, from its
+     * stratified variance : the sum over the strata of w^2 p (1 - p) / n,
+     * w being the share of the configurations the stratum holds, p its
+     * success rate and n the number of configurations it tested. The
+     * interval is the one of a plain sample with the same rate and
+     * variance, of effective size p (1 - p) / variance, on the share of
+     * the configurations which are not ties, which are then added back.
+     *
+     * When every stratum is all correct, or all wrong, the variance is 0
+     * and the effective size is the one of strata sharing a single rate,
+     * 1 / sum of w^2 / n. A stratum which tested nothing could have any
+     * rate, and leaves the interval as wide as it gets.
+     */
+    pub fn confidence_interval(&self, interval : Interval) -> (f64, f64) {
+        let ties = self.ties_share();
+        let sampled = 1.0 - ties;
+        if self.strata.iter().any(|(_, outcome)| outcome.tested == 0) {
+            return (ties, 1.0);
+        }
+
+        // the shares of the strata among the configurations which are not ties
+        let strata : Vec<(f64, f64, f64)> = self.strata.iter()
+            .map(|(ones, outcome)| {
+                (density_share(self.size, *ones) / sampled, outcome.success_rate(), outcome.tested as f64)
+            })
+            .collect();
+        let rate : f64 = strata.iter().map(|&(w, p, _)| w * p).sum();
+        let variance : f64 = strata.iter().map(|&(w, p, n)| w * w * p * (1.0 - p) / n).sum();
+        let tested = if variance > 0.0 {
+            rate * (1.0 - rate) / variance
+        }
+        else {
+            1.0 / strata.iter().map(|&(w, _, n)| w * w / n).sum::<f64>()
+        };
+
+        let (low, high) = interval.bounds(rate * tested, tested);
+        (ties + sampled * low, ties + sampled * high)
+    }
+
+    /**
+     * Returns the share of the configurations of the size which are ties.
+     */
+    fn ties_share(&self) -> f64 {
+        if self.size.is_multiple_of(2) { density_share(self.size, self.size / 2) } else { 0.0 }
+    }
+
+    fn weighted(&self, f : impl Fn(&SampleOutcome) -> f64) -> f64 {
+        self.ties_share() +
+            self.strata.iter().map(|(ones, outcome)| density_share(self.size, *ones) * f(outcome)).sum::<f64>()
+    }
+}
+
+/**
+ * Returns the share of the configurations of the given size which have
+ * exactly the given number of ones, binomial(size, ones) / 2^size,
+ * computed with logarithms so it does not overflow on large sizes.
+ */
+pub(crate) fn density_share(size : u32, ones : u32) -> f64 {
+    let ones = ones.min(size - ones);
+    let log_binomial : f64 = (0..ones).map(|k| ((size - k) as f64).ln() - ((k + 1) as f64).ln()).sum();
+    (log_binomial - size as f64 * 2f64.ln()).exp()
+}
+
+/**
+ * Same as sample, but stratified by density : each number of ones gets
+ * its own sample, except the ties which are always correct. The count is
+ * spread on the strata with weights decreasing away from density 1/2,
+ * as 1 / (1 + |2 ones - size|), and each stratum gets at least one
+ * configuration so that no density is left out : the samples concentrate
+ * where the failures are expected, and the extremes are still covered.
+ */
+#[cfg(feature = "search")]
+pub fn sample_stratified(size : u32, count : u64, seed : u64, backend : Backend) -> StratifiedOutcome {
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let strata = (0..=size).filter(|&ones| 2 * ones != size);
+    let weight = |ones : u32| 1.0 / (1.0 + (2 * ones).abs_diff(size) as f64);
+    let total : f64 = strata.clone().map(weight).sum();
+
+    let strata = strata
+        .map(|ones| {
+            let count = 1 + (count as f64 * weight(ones) / total) as u64;
+            (ones, sample_from(size, count, Some(ones), backend, &mut rng))
+        })
+        .collect();
+
+    StratifiedOutcome { size, strata }
+}
+
+/**
+ * The options of write_sample.
+ */
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SampleOptions {
+    // How many configurations are tested on each size.
+    pub count : u64,
+    // The seed of the draws, the same for all the sizes.
+    pub seed : u64,
+    // Use sample_stratified instead of sample?
+    pub stratified : bool,
+    // The confidence interval written for the success rates.
+    pub interval : Interval,
+    // The configuration type the samples run on, see Backend.
+    pub backend : Backend,
+}
+
+/**
+ * Runs sample, or sample_stratified, on each size from min to max, all
+ * with the same seed so a size can be sampled again on its own, and
+ * writes a line per size with the failures, the estimated success rate
+ * and its confidence interval of the kind of the options. Returns true if
+ * no failure was found.
+ */
+#[cfg(feature = "search")]
+pub fn write_sample(min : u32, max : u32, options : &SampleOptions, w : &mut dyn Write) -> io::Result<bool> {
+    let SampleOptions { count, seed, stratified, interval, backend } = *options;
+    let how = if stratified { "stratified by density" } else { "uniformly" };
+    writeln!(w, "sampling {count} configurations of each size from {min} to {max} {how} with seed {seed}")?;
+    writeln!(w, "size   tested failures success rate  95% interval ({interval:?})")?;
+
+    let mut clean = true;
+    for size in min..=max {
+        let (tested, failures, rate, (low, high), failing) = if stratified {
+            let outcome = sample_stratified(size, count, seed, backend);
+            let (tested, failures) = outcome.totals();
+            (tested, failures, outcome.success_rate(), outcome.confidence_interval(interval), outcome.strata)
+        }
+        else {
+            let outcome = sample(size, count, seed, backend);
+            (outcome.tested, outcome.failures, outcome.success_rate(), outcome.confidence_interval(interval),
+                vec![(0, outcome)])
+        };
+        writeln!(w, "{size:4} {tested:8} {failures:8} {rate:12.6}  [{low:.6}, {high:.6}]")?;
+
+        for (ones, outcome) in failing {
+            if let Some(failure) = &outcome.first_failure {
+                if stratified {
+                    writeln!(w, "     {} of {} with {ones} ones failed, first : {failure}",
+                        outcome.failures, outcome.tested)?;
+                }
+                else {
+                    writeln!(w, "     first failure : {failure}")?;
+                }
+                clean = false;
+            }
+        }
+    }
+
+    Ok(clean)
+}
@@ -0,0 +1,1086 @@
+/*
+ * The exhaustive searches over all the configurations of a size : their
+ * options and orders, the reductions by symmetry, the parallel iteration
+ * with its progress and checkpoints, and the reports of the sizes searched
+ * and of the counter examples found.
+ */
+
+use std::fs;
+use std::io::{self, Write};
+#[cfg(feature = "search")]
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "search")]
+use std::sync::{atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering}, Mutex};
+
+#[cfg(feature = "search")]
+use indicatif::{ProgressBar, ProgressStyle};
+#[cfg(feature = "search")]
+use rayon::iter::{
+    IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelBridge,
+    ParallelIterator,
+};
+
+use crate::{Backend, ConvergenceMap, PrintOptions, ResultsDb, Shard};
+#[cfg(feature = "search")]
+use crate::{
+    append_results_log, append_stats_row, append_to_corpus, bracelet_count, canonical_form,
+    complement_compatible, dihedral_form, expected_convergence_bound, interrupted, necklace_count,
+    orbit_size, reflection_compatible, rotation_compatible, write_execution, write_execution_png,
+    write_shard_result, Configuration, Configuration64, Necklaces, Rule, Sequential, SizeReport,
+    TiePolicy, Verdict, REFLECTION_CHECK_SIZE,
+};
+// the profile measures Configuration::check instead
+#[cfg(all(feature = "search", not(feature = "profile")))]
+use crate::fixed_check;
+
+/**
+ * The options of the exhaustive search.
+ */
+#[derive(Default)]
+pub struct SearchOptions {
+    // How counter examples are printed.
+    pub print : PrintOptions,
+    // If set, the seed of the order in which configurations are visited.
+    pub shuffle_seed : Option<u64>,
+    // If set, the corpus file counter examples are appended to.
+    pub corpus : Option<PathBuf>,
+    // Return the smallest counter example instead of any of them?
+    pub deterministic : bool,
+    // Visit the configurations in Gray code order? Ignored when shuffling.
+    pub gray_code : bool,
+    // If set, called with the progress of the search, see ProgressCallback.
+    pub progress : Option<ProgressCallback>,
+    // If set, the directory the diagrams of counter examples are saved to.
+    pub dump_images : Option<PathBuf>,
+    // If set, searches stop early once this instant has passed.
+    pub deadline : Option<std::time::Instant>,
+    // If set, the configurations which are clean in this map are skipped
+    // by the searches of its size.
+    pub known_clean : Option<ConvergenceMap>,
+    // Use is_correct_fast instead of is_correct?
+    pub fast_extremes : bool,
+    // The configuration type fuzz runs on, see Backend.
+    pub backend : Backend,
+    // Which configurations the searches test, see Reduction.
+    pub reduction : Reduction,
+    // If set, the file the progress of the searches is saved to, see
+    // Checkpoint.
+    pub checkpoint : Option<PathBuf>,
+    // If set, where search_all and search_size resume, instead of
+    // starting over.
+    pub resume : Option<Checkpoint>,
+    // If set, the searches only visit the slice of each size of this
+    // shard, see Shard.
+    pub shard : Option<Shard>,
+    // How search_size and search_all report their results.
+    pub output : OutputFormat,
+    // If set, the file a row of statistics is appended to for each size
+    // searched, see append_stats_row.
+    pub stats_csv : Option<PathBuf>,
+    // If set, the log each search of a size appends its result to, see
+    // append_results_log.
+    pub results_log : Option<PathBuf>,
+    // If set, the database the searches of the sizes are recorded in, see
+    // ResultsDb.
+    pub db : Option<ResultsDb>,
+}
+
+/**
+ * How search_size and search_all report their results.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    // Lines of text, with the execution of the counter examples.
+    #[default]
+    Text,
+    // One JSON object per line : a SizeReport for each size, and a summary
+    // at the end of search_all. The searches count the updates of the
+    // executions for them, which makes them a bit slower.
+    Json,
+}
+
+/**
+ * Where a search stopped : the sizes below size are clean, and so are the
+ * configurations of size whose index in the order of the search is below
+ * next, see search_configurations_in. The order is described by
+ * search_order, as the indices mean nothing in another order.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub size : u32,
+    pub next : u64,
+    pub order : String,
+}
+
+impl Checkpoint {
+    /**
+     * Reads a checkpoint from a file written by the write method.
+     */
+    pub fn read(path : impl AsRef<Path>) -> io::Result<Self> {
+        let invalid = |message : String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+        let text = fs::read_to_string(path)?;
+        let line = text.lines().find(|line| ! line.starts_with('#'))
+            .ok_or_else(|| invalid("empty checkpoint".to_string()))?;
+        let mut fields = line.splitn(3, ' ');
+        let mut number = |name : &str| fields.next().and_then(|field| field.parse().ok())
+            .ok_or_else(|| invalid(format!("invalid {name} in checkpoint line {line:?}")));
+
+        let size = number("size")? as u32;
+        let next = number("index")?;
+        let order = fields.next().unwrap_or("").to_string();
+
+        Ok(Checkpoint { size, next, order })
+    }
+
+    /**
+     * Writes the checkpoint to a file, replacing it at once : the
+     * checkpoint is written to a temporary file next to it first, so a
+     * crash while writing leaves the previous checkpoint intact.
+     */
+    pub fn write(&self, path : impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let temporary = path.with_extension("tmp");
+
+        let mut file = fs::File::create(&temporary)?;
+        writeln!(file, "# size, index the search of that size resumes at, and order of the search")?;
+        writeln!(file, "{} {} {}", self.size, self.next, self.order)?;
+        file.sync_all()?;
+
+        fs::rename(temporary, path)
+    }
+}
+
+/**
+ * Describes the order in which the searches of the options visit the
+ * configurations, so a checkpoint is only resumed in the same order.
+ */
+pub fn search_order(options : &SearchOptions) -> String {
+    let order = match options.shuffle_seed {
+        Some(seed) => format!("shuffled {seed}"),
+        None if options.gray_code => "gray".to_string(),
+        None => "linear".to_string(),
+    };
+    let shard = match &options.shard {
+        Some(shard) => format!(" shard {}/{}", shard.index, shard.count),
+        None => String::new(),
+    };
+    format!("{:?} {order}{shard}", options.reduction).to_lowercase()
+}
+
+/**
+ * Which configurations of a size the exhaustive searches test. All but
+ * Full assume that a configuration gets the same verdict as its
+ * complement, and some as its rotations or mirror images, which is only
+ * checked up to REFLECTION_CHECK_SIZE, see check_complement_symmetry and
+ * check_rotation_symmetry : above it, only Full is an exhaustive proof.
+ * If complement_compatible does not hold, the searches fall back to Full
+ * whatever the reduction.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Reduction {
+    // All the configurations, to cross-check the other reductions.
+    Full,
+    // The configurations whose last cell is a 0, one of each pair of
+    // complements, see all_configurations : half of them. The rule is not
+    // symmetric between 0 and 1 (see complement_asymmetries), so that
+    // complements get the same verdict is an unproven assumption above
+    // REFLECTION_CHECK_SIZE.
+    #[default]
+    Complement,
+    // The canonical forms only, one of each class of rotations and
+    // complements, see Necklaces. This tests about size times fewer
+    // configurations, but in the order of Necklaces whatever the options.
+    // Ignored above Configuration::MAX_SIZE.
+    // The sweep starts at cell 0, so the rotations of a configuration run
+    // different executions : that they get the same verdict is not proven,
+    // only checked by rotation_compatible up to REFLECTION_CHECK_SIZE
+    // before each search, which falls back to Complement if it does not
+    // hold. A clean search is thus reduced, not exhaustive, and ends with
+    // SearchStatus::Reduced rather than Clean.
+    Rotation,
+    // The dihedral forms only, one of each class of rotations, mirror
+    // images and complements, see dihedral_form : about half as many
+    // configurations as Rotation. The rule is not symmetric under
+    // reflection, and that the executions of the mirror images converge
+    // in as many updates and to the same value is an unproven heuristic :
+    // reflection_compatible only checks it up to REFLECTION_CHECK_SIZE
+    // before each search, falling back to Rotation if it does not hold,
+    // and the searches of larger sizes start with a warning. Only Full
+    // proves a size clean.
+    Reflection,
+}
+
+/**
+ * How far a search has gone, as given to its progress callback.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchProgress {
+    // how many configurations were tested so far, out of total
+    pub tested : u64,
+    pub total : u64,
+    // how many counter examples were found so far, and the smallest of
+    // them. Unless the search is deterministic, it stops soon after the
+    // first one, so this is mostly useful with deterministic searches.
+    pub failures : u64,
+    pub smallest : Option<u64>,
+}
+
+/**
+ * A function following the progress of a search. It is called from the
+ * threads of the search, so it has to be Sync, but only about once every
+ * PROGRESS_INTERVAL configurations, and once more at the end of the search
+ * with the final counts, whose tested count is below the total if the
+ * search stopped early. The first call of each search has a tested count
+ * of 0.
+ */
+pub type ProgressCallback = Box<dyn Fn(&SearchProgress) + Send + Sync>;
+
+/**
+ * The number of configurations tested between two calls of the progress
+ * callback of a search.
+ */
+pub const PROGRESS_INTERVAL : u64 = 1 << 12;
+
+/**
+ * Returns a progress callback drawing an indicatif progress bar, which is
+ * how the command line shows the progress of searches. A new bar is
+ * started at the beginning of each search.
+ */
+#[cfg(feature = "search")]
+pub fn progress_bar() -> ProgressCallback {
+    let style = ProgressStyle::with_template("[{eta}] {pos:10}/{len:10} {bar:40} {msg}").unwrap();
+    let bar : Mutex<Option<ProgressBar>> = Mutex::new(None);
+
+    Box::new(move |progress| {
+        let mut bar = bar.lock().unwrap();
+        if progress.tested == 0 || bar.is_none() {
+            *bar = Some(ProgressBar::new(progress.total).with_style(style.clone()));
+        }
+        if let Some(bar) = bar.as_ref() {
+            bar.set_position(progress.tested);
+            if let Some(smallest) = progress.smallest {
+                bar.set_message(format!("{} counter examples, smallest {smallest:#X}", progress.failures));
+            }
+            if progress.tested == progress.total {
+                bar.finish();
+            }
+        }
+    })
+}
+
+/**
+ * A pseudo-random permutation of the numbers of `bits` bits, chosen by
+ * the seed. Each round multiplies by an odd number, adds a constant and
+ * xors the number with its own high bits, all modulo 1 << bits, and each
+ * of these operations is a bijection, so every number below 1 << bits is
+ * still visited exactly once.
+ */
+#[cfg(feature = "search")]
+fn shuffle_index(k : u32, bits : u32, seed : u64) -> u32 {
+    let mask = (1u64 << bits) - 1;
+    let mut x = k as u64;
+    let mut key = seed;
+
+    for _ in 0..3 {
+        // splitmix64, to derive the keys of each round from the seed
+        key = key.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = key;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+
+        x = x.wrapping_mul(z | 1) & mask;
+        x = x.wrapping_add(z >> 32) & mask;
+        x ^= x >> (bits / 2 + 1);
+    }
+
+    x as u32
+}
+
+/**
+ * The k-th number in Gray code order : consecutive numbers in that order
+ * differ by a single bit, and the numbers of `bits` bits are sent to
+ * themselves, so visiting gray_code(k) for all k below 1 << bits visits
+ * the same numbers as the linear order.
+ */
+#[cfg(feature = "search")]
+#[inline]
+pub(crate) fn gray_code(k : u32) -> u32 {
+    k ^ (k >> 1)
+}
+
+/**
+ * Returns, as a parallel iterator, a fresh configuration for each value
+ * of the half-open range 0..1 << (size - 1), in increasing order.
+ *
+ * These are the configurations whose last cell is a 0 : the last cell is
+ * left out of the range because the rule is symmetric between 0 and 1,
+ * so the complement of any configuration ending with a 1 behaves the same
+ * way as the configuration itself, and needs not be tested again.
+ */
+#[cfg(feature = "search")]
+pub fn all_configurations(size : u32) -> impl IndexedParallelIterator<Item = Configuration> {
+    (0..1 << (size - 1))
+        .into_par_iter()
+        .map(move |k| Configuration::new(k, size))
+}
+
+/**
+ * This function iterates through all the configurations of a given size,
+ * and returns any counter-example on which the is_correct method returns
+ * false. If no counter example is found, it returns None instead.
+ *
+ * This function makes uses of parallel iterators for more speed.
+ * If the options contain a shuffle seed, the configurations are visited
+ * in a pseudo-random order instead of roughly increasing order, which
+ * finds clustered counter examples sooner on average.
+ *
+ * Which counter example is returned depends on the scheduling of the
+ * threads, unless the options ask for a deterministic search : the
+ * smallest counter example is then returned, at the price of testing
+ * every configuration even after a counter example was found.
+ */
+#[cfg(feature = "search")]
+pub fn find_counter_example(size : u32, options : &SearchOptions) -> Option<u64> {
+    search_configurations(size, options).counter_example
+}
+
+/**
+ * The outcome of search_configurations.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchOutcome {
+    // the counter example found, if any
+    pub counter_example : Option<u64>,
+    // how many configurations were visited, out of total
+    pub tested : u64,
+    pub total : u64,
+    // how many of the visited configurations were skipped as known clean
+    pub skipped : u64,
+    // did the search stop at the deadline of the options? If so, it is
+    // only partial, and configurations were left out.
+    pub timed_out : bool,
+    // did the search stop because the process was interrupted? Same as
+    // timed_out, see install_interrupt_handler.
+    pub interrupted : bool,
+    // the updates the executions the search ran took, if the options
+    // count them, see OutputFormat::Json and SearchOptions::stats_csv
+    pub iterations : Option<IterationStats>,
+}
+
+/**
+ * The number of updates the executions of a search took to converge, the
+ * ties, which are not run, being left out.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IterationStats {
+    // how many executions were counted
+    pub runs : u64,
+    // their updates, in total and the most of any of them
+    pub total : u64,
+    pub max : u32,
+}
+
+impl IterationStats {
+    /**
+     * Returns the mean number of updates, if any execution was counted.
+     */
+    pub fn mean(&self) -> Option<f64> {
+        (self.runs > 0).then(|| self.total as f64 / self.runs as f64)
+    }
+
+    /**
+     * Returns the statistics of the executions of both searches.
+     */
+    pub fn merge(&self, other : &IterationStats) -> IterationStats {
+        IterationStats {
+            runs : self.runs + other.runs,
+            total : self.total + other.total,
+            max : self.max.max(other.max),
+        }
+    }
+}
+
+/**
+ * Same as find_counter_example, but also tells how far the search went.
+ * If the options have a deadline, the search checks the time every
+ * PROGRESS_INTERVAL configurations, and once the deadline has passed all
+ * the threads stop taking new configurations, so the search ends early
+ * with whatever counter example it found so far.
+ *
+ * If the options have a known clean map of the same size, the
+ * configurations it says are clean are skipped instead of checked. See
+ * ConvergenceMap::is_clean for what clean means there.
+ *
+ * Unless the options ask for another Reduction, only the configurations
+ * whose last cell is a 0 are tested, as with all_configurations.
+ *
+ * The sizes up to Configuration::MAX_SIZE are checked with fixed_check,
+ * on a FixedConfiguration of the size. Sizes above Configuration::MAX_SIZE,
+ * up to Configuration64::MAX_SIZE, are searched with Configuration64, in
+ * increasing order whatever the options.
+ */
+#[cfg(feature = "search")]
+pub fn search_configurations(size : u32, options : &SearchOptions) -> SearchOutcome {
+    search_configurations_in(size, options, search_range(size, options))
+}
+
+/**
+ * Returns the indices of the configurations of a size the searches of the
+ * options visit : all of them, or the slice of their shard.
+ */
+#[cfg(feature = "search")]
+pub(crate) fn search_range(size : u32, options : &SearchOptions) -> Range<u64> {
+    match &options.shard {
+        Some(shard) => shard.range(search_length(size, options)
+            .expect("the reductions by rotation have no indices to shard")),
+        None => 0..u64::MAX,
+    }
+}
+
+/**
+ * Returns the reduction the searches of the options actually use, which
+ * falls back to another one when the symmetry it relies on is not
+ * verified, see Reduction.
+ */
+#[cfg(feature = "search")]
+fn effective_reduction(options : &SearchOptions) -> Reduction {
+    match options.reduction {
+        _ if ! complement_compatible() => Reduction::Full,
+        Reduction::Rotation | Reduction::Reflection if ! rotation_compatible() => Reduction::Complement,
+        Reduction::Reflection if ! reflection_compatible() => Reduction::Rotation,
+        reduction => reduction,
+    }
+}
+
+/**
+ * Returns true if the searches of a size with the options only test one
+ * configuration of each class of rotations, see Reduction::Rotation : a
+ * clean result is then reduced, not exhaustive.
+ */
+#[cfg(feature = "search")]
+pub fn is_reduced_search(size : u32, options : &SearchOptions) -> bool {
+    matches!(effective_reduction(options), Reduction::Rotation | Reduction::Reflection) &&
+        size <= Configuration::<u32>::MAX_SIZE
+}
+
+/**
+ * Returns how many configurations search_configurations visits on a size,
+ * in an order where they can be told apart by their index, see
+ * search_configurations_in. The reductions by rotation have no such
+ * order, and return None.
+ */
+#[cfg(feature = "search")]
+pub fn search_length(size : u32, options : &SearchOptions) -> Option<u64> {
+    match effective_reduction(options) {
+        Reduction::Full => Some(1 << size),
+        Reduction::Complement => Some(1 << (size - 1)),
+        Reduction::Rotation | Reduction::Reflection if size > Configuration::<u32>::MAX_SIZE => Some(1 << (size - 1)),
+        Reduction::Rotation | Reduction::Reflection => None,
+    }
+}
+
+/**
+ * Same as search_configurations, but only visits the configurations whose
+ * index in the order of the search is within the given range, which is
+ * cut to search_length. This is how a search is split into parts which
+ * can be run one after the other, see search_all. With the reductions by
+ * rotation, which have no indices, the range is ignored.
+ */
+#[cfg(feature = "search")]
+pub fn search_configurations_in(size : u32, options : &SearchOptions, indices : Range<u64>) -> SearchOutcome {
+    let reduction = effective_reduction(options);
+    // the last cell is only enumerated without reduction, the other
+    // configurations being the complements of those where it is 0
+    let bits = if reduction == Reduction::Full { size } else { size - 1 };
+    let indices = indices.start.min(1 << bits)..indices.end.min(1 << bits);
+    let total = indices.end - indices.start;
+
+    // the executions are only counted for the reports which need it, the
+    // fixed configurations being faster
+    let counting = options.output == OutputFormat::Json || options.stats_csv.is_some() || options.db.is_some();
+    let (runs, total_iterations, max_iterations) = (AtomicU64::new(0), AtomicU64::new(0), AtomicU32::new(0));
+    let count = |(verdict, iterations) : (Verdict, Option<u32>)| {
+        if let Some(iterations) = iterations {
+            runs.fetch_add(1, Ordering::Relaxed);
+            total_iterations.fetch_add(iterations as u64, Ordering::Relaxed);
+            max_iterations.fetch_max(iterations, Ordering::Relaxed);
+        }
+        verdict
+    };
+    let counted = |outcome : SearchOutcome| SearchOutcome {
+        iterations : counting.then(|| IterationStats {
+            runs : runs.load(Ordering::Relaxed),
+            total : total_iterations.load(Ordering::Relaxed),
+            max : max_iterations.load(Ordering::Relaxed),
+        }),
+        ..outcome
+    };
+
+    if size > Configuration::<u32>::MAX_SIZE {
+        // the shuffled and Gray code orders, the maps, the fast path and
+        // the reductions by rotation only exist for the sizes of
+        // Configuration
+        let values = indices.into_par_iter();
+        return counted(run_search(options, total, values, |k| {
+            let mut x = Configuration64::new(k, size);
+            Some(if counting { count(x.check_counting()) == Verdict::Correct } else { x.is_correct() })
+        }));
+    }
+
+    let known_clean = options.known_clean.as_ref().filter(|map| map.size == size);
+
+    // the profile measures Configuration::check, so it has to be called
+    #[cfg(not(feature = "profile"))]
+    let check = fixed_check(size);
+    #[cfg(feature = "profile")]
+    let check = |value| Configuration::new(value, size).check();
+
+    let is_correct = |k : u64| {
+        let x = Configuration::new(k as u32, size);
+        if known_clean.is_some_and(|map| map.is_clean(x.value)) {
+            return None;
+        }
+        if options.fast_extremes && x.is_trivially_correct() {
+            return Some(true);
+        }
+        if counting {
+            return Some(count(Configuration::new(k as u32, size).check_counting()) == Verdict::Correct);
+        }
+        Some(check(x.value) == Verdict::Correct)
+    };
+
+    if reduction == Reduction::Reflection {
+        let values = Necklaces::new(size)
+            .par_bridge()
+            .filter(|&k| dihedral_form(k, size) == k)
+            .map(|k| k as u64);
+        return counted(run_search(options, bracelet_count(size), values, is_correct));
+    }
+    if reduction == Reduction::Rotation {
+        let values = Necklaces::new(size)
+            .par_bridge()
+            .filter(|&k| canonical_form(k, size) == k)
+            .map(|k| k as u64);
+        return counted(run_search(options, necklace_count(size), values, is_correct));
+    }
+
+    let values = (indices.start as u32..indices.end as u32)
+        .into_par_iter()
+        .map(|k| match options.shuffle_seed {
+            Some(seed) => shuffle_index(k, bits, seed),
+            None if options.gray_code => gray_code(k),
+            None => k,
+        })
+        .map(|k| k as u64);
+
+    counted(run_search(options, total, values, is_correct))
+}
+
+/**
+ * The number of configurations a thread of run_search goes through before
+ * adding them to the shared count.
+ */
+#[cfg(feature = "search")]
+const COUNT_BATCH : u64 = 1 << 8;
+
+/**
+ * The configurations a thread of run_search went through and did not add
+ * to the shared count yet : the threads only add to it every COUNT_BATCH
+ * configurations, and when they are done, so that they do not all contend
+ * on it.
+ */
+#[cfg(feature = "search")]
+struct LocalCount<'a> {
+    count : u64,
+    shared : &'a AtomicU64,
+}
+
+#[cfg(feature = "search")]
+impl LocalCount<'_> {
+    /**
+     * Adds the local count to the shared one, and returns the shared count
+     * before and after.
+     */
+    fn flush(&mut self) -> Range<u64> {
+        let before = self.shared.fetch_add(self.count, Ordering::Relaxed);
+        let after = before + self.count;
+        self.count = 0;
+        before..after
+    }
+}
+
+#[cfg(feature = "search")]
+impl Drop for LocalCount<'_> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/**
+ * The search loop shared by all the sizes : tests the configurations whose
+ * values are given, total of them, in parallel, with is_correct, which
+ * returns None for the configurations it skips. Handles the progress, the
+ * deadline and the determinism of the options, see search_configurations.
+ */
+#[cfg(feature = "search")]
+fn run_search(options : &SearchOptions, total : u64, values : impl ParallelIterator<Item = u64>,
+    is_correct : impl Fn(u64) -> Option<bool> + Sync + Send) -> SearchOutcome {
+    let done = AtomicU64::new(0);
+    let timed_out = AtomicBool::new(false);
+    let stopped_by_interrupt = AtomicBool::new(false);
+    let skipped = AtomicU64::new(0);
+    let found = AtomicU64::new(0);
+    // no configuration has this value, see Configuration64::MAX_SIZE
+    let smallest = AtomicU64::new(u64::MAX);
+    let report = |tested| if let Some(progress) = &options.progress {
+        progress(&SearchProgress {
+            tested, total,
+            failures : found.load(Ordering::Relaxed),
+            smallest : Some(smallest.load(Ordering::Relaxed)).filter(|&k| k != u64::MAX),
+        });
+    };
+
+    report(0);
+
+    let failures = values
+        .take_any_while(|_| {
+            if interrupted() {
+                stopped_by_interrupt.store(true, Ordering::Relaxed);
+            }
+            ! timed_out.load(Ordering::Relaxed) && ! stopped_by_interrupt.load(Ordering::Relaxed)
+        })
+        .map_init(|| LocalCount { count : 0, shared : &done }, |local, k| {
+            local.count += 1;
+            if local.count == COUNT_BATCH {
+                let counted = local.flush();
+                if counted.start / PROGRESS_INTERVAL != counted.end / PROGRESS_INTERVAL {
+                    report(counted.end);
+                    if options.deadline.is_some_and(|deadline| std::time::Instant::now() >= deadline) {
+                        timed_out.store(true, Ordering::Relaxed);
+                    }
+                }
+            }
+            k
+        })
+        .filter(|&k| match is_correct(k) {
+            None => {
+                skipped.fetch_add(1, Ordering::Relaxed);
+                false
+            },
+            Some(correct) => ! correct, // we keep the ones that failed
+        })
+        .inspect(|&k| {
+            found.fetch_add(1, Ordering::Relaxed);
+            smallest.fetch_min(k, Ordering::Relaxed);
+        });
+
+    let counter_example = if options.deterministic {
+        failures.min() // has to go through everything
+    }
+    else {
+        failures
+            .take_any(1)
+            //.take(1)
+            .collect::<Vec<_>>()
+            .first()
+            .copied() // and return the first one, if there is any
+    };
+
+    let tested = done.load(Ordering::Relaxed);
+    report(tested);
+
+    SearchOutcome {
+        counter_example, tested, total,
+        skipped : skipped.into_inner(),
+        timed_out : timed_out.into_inner(),
+        interrupted : stopped_by_interrupt.into_inner(),
+        iterations : None,
+    }
+}
+
+/**
+ * Runs check on each of the given (size, value) pairs, in parallel,
+ * and returns the verdicts in the same order as the pairs.
+ */
+#[cfg(feature = "search")]
+pub fn check_batch(pairs : &[(u32, u32)]) -> Vec<Verdict> {
+    pairs.par_iter()
+        .map(|&(size, value)| {
+            Configuration::new(value, size).check()
+        })
+        .collect()
+}
+
+/**
+ * How a search of one or several sizes ended, from the best to the worst :
+ * a counter example settles the question even if the search is partial.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SearchStatus {
+    // Every configuration searched is correct.
+    Clean,
+    // Every configuration searched is correct, but only one of each class
+    // of rotations was, see is_reduced_search : not a proof that the size
+    // is clean.
+    Reduced,
+    // No counter example, but the search stopped at its deadline or was
+    // interrupted before the end, see interrupted.
+    Incomplete,
+    // A counter example was found.
+    CounterExample,
+}
+
+/**
+ * Helper function which calls find_counter_example, and if a counter example
+ * is found, writes a nice error about it, as well as the execution of
+ * the counter example, for inspection by the user.
+ * Returns how the search ended, Clean only if the size is clean.
+ */
+#[cfg(feature = "search")]
+pub fn search_size(size : u32, options : &SearchOptions, w : &mut dyn Write) -> io::Result<SearchStatus> {
+    let from = options.resume.as_ref().filter(|resume| resume.size == size).map_or(0, |resume| resume.next);
+    search_size_from(size, from, options, w)
+}
+
+/**
+ * How many configurations are searched between two saves of the
+ * checkpoint : a few minutes of work on the largest sizes.
+ */
+#[cfg(feature = "search")]
+const CHECKPOINT_INTERVAL : u64 = 1 << 26;
+
+/**
+ * Same as search_size, but starts at the given index in the order of the
+ * search, the configurations before it being known to be clean. If the
+ * options have a checkpoint file, the size is searched in parts of
+ * CHECKPOINT_INTERVAL configurations, and the checkpoint is saved after
+ * each of them, and once the size is clean. The reductions by rotation
+ * are searched in one part, see search_length.
+ */
+#[cfg(feature = "search")]
+pub fn search_size_from(size : u32, from : u64, options : &SearchOptions, w : &mut dyn Write)
+    -> io::Result<SearchStatus> {
+    let length = search_length(size, options);
+    let range = search_range(size, options);
+    let end = length.map_or(u64::MAX, |length| range.end.min(length));
+    let part = if options.checkpoint.is_some() && length.is_some() { CHECKPOINT_INTERVAL } else { u64::MAX };
+    let save = |next| match &options.checkpoint {
+        Some(path) => Checkpoint { size, next, order : search_order(options) }.write(path),
+        None => Ok(()),
+    };
+    let name = match &options.shard {
+        Some(shard) => format!("size {size} shard {}/{}", shard.index, shard.count),
+        None => format!("size {size}"),
+    };
+    let json = options.output == OutputFormat::Json;
+    let reduced = is_reduced_search(size, options);
+    let started = std::time::Instant::now();
+    let started_at = std::time::SystemTime::now();
+
+    if reduced && size > REFLECTION_CHECK_SIZE && ! json {
+        writeln!(w, "{name} : warning, --reduce {} is an unproven heuristic above size {REFLECTION_CHECK_SIZE}, \
+            only --reduce full proves a size clean", format!("{:?}", effective_reduction(options)).to_lowercase())?;
+    }
+
+    // the configurations of the slice before from are known to be clean
+    let first = from.max(range.start).min(end);
+    let mut next = first;
+    let (mut tested, mut skipped) = (first - range.start, 0);
+    let mut iterations : Option<IterationStats> = None;
+    let (start, outcome) = loop {
+        let start = next;
+        next = start.saturating_add(part).min(end);
+        let outcome = search_configurations_in(size, options, start..next);
+        tested += outcome.tested;
+        skipped += outcome.skipped;
+        if let Some(counted) = outcome.iterations {
+            iterations = Some(counted.merge(&iterations.unwrap_or_default()));
+        }
+
+        if outcome.counter_example.is_some() || outcome.timed_out || outcome.interrupted || next >= end {
+            break (start, outcome);
+        }
+        save(next)?;
+    };
+    let total = length.map_or(outcome.total, |_| end - range.start);
+
+    let report = SizeReport {
+        size, tested, total, skipped, iterations,
+        seconds : started.elapsed().as_secs_f64(),
+        counter_examples : outcome.counter_example.into_iter().collect(),
+        result : if outcome.counter_example.is_some() { "counter_example" }
+            else if outcome.interrupted { "interrupted" }
+            else if outcome.timed_out { "time_limit" }
+            else if reduced { "clean_reduced" }
+            else { "clean" },
+    };
+    if let Some(path) = &options.stats_csv {
+        append_stats_row(path, &report)?;
+    }
+    if let Some(path) = &options.results_log {
+        append_results_log(path, started_at, options, first..range.start + total, &report)?;
+    }
+    if let Some(db) = &options.db {
+        db.record(started_at, first..range.start + total, &report)?;
+    }
+
+    if json {
+        writeln!(w, "{}", report.to_json())?;
+    }
+    else if options.known_clean.as_ref().is_some_and(|map| map.size == size) {
+        writeln!(w, "{name} : {skipped} skipped as known clean, {} checked",
+            tested - (first - range.start) - skipped)?;
+    }
+
+    if let Some(result) = outcome.counter_example {
+        write_shard_result(size, options, tested, total, &format!("counter example {result:#X}"))?;
+        if ! json {
+            write_counter_example(size, result, options, w)?;
+        }
+        Ok(SearchStatus::CounterExample)
+    }
+    else if outcome.interrupted {
+        // the part which was interrupted is searched again on resume
+        save(start)?;
+        write_shard_result(size, options, tested, total, "interrupted")?;
+        if json {
+            return Ok(SearchStatus::Incomplete);
+        }
+        writeln!(w, "{name} : interrupted after {tested} of {total} configurations, \
+            no counter example so far (partial result)")?;
+        match &options.checkpoint {
+            Some(path) => writeln!(w, "progress saved, resume at index {start} with --checkpoint {} --resume",
+                path.display())?,
+            None => writeln!(w, "progress not saved, run with --checkpoint FILE to be able to resume")?,
+        }
+        Ok(SearchStatus::Incomplete)
+    }
+    else if outcome.timed_out {
+        write_shard_result(size, options, tested, total, "time limit reached")?;
+        if json {
+            return Ok(SearchStatus::Incomplete);
+        }
+        writeln!(w, "{name} : time limit reached after {tested} of {total} configurations, \
+            no counter example so far (partial result)")?;
+        Ok(SearchStatus::Incomplete)
+    }
+    else {
+        save_clean(size, options)?;
+        write_shard_result(size, options, tested, total, "clean")?;
+        if reduced {
+            if ! json {
+                writeln!(w, "{name} clean (reduced, not exhaustive : only one configuration per rotation class)")?;
+            }
+            return Ok(SearchStatus::Reduced);
+        }
+        if ! json {
+            writeln!(w, "{name} clean")?;
+        }
+        Ok(SearchStatus::Clean)
+    }
+}
+
+/**
+ * Saves to the checkpoint file of the options, if any, that the given
+ * size is clean : the search resumes at the start of the next size.
+ */
+#[cfg(feature = "search")]
+fn save_clean(size : u32, options : &SearchOptions) -> io::Result<()> {
+    match &options.checkpoint {
+        Some(path) => Checkpoint { size : size + 1, next : 0, order : search_order(options) }.write(path),
+        None => Ok(()),
+    }
+}
+
+/**
+ * Writes a nice error about a counter example, as well as its execution,
+ * and appends it to the corpus of the options, if any. Above
+ * Configuration::MAX_SIZE, the corpus, the diagram and the analyses of
+ * the counter example are left out, see write_wide_counter_example.
+ */
+#[cfg(feature = "search")]
+pub fn write_counter_example(size : u32, value : u64, options : &SearchOptions, w : &mut dyn Write)
+    -> io::Result<()> {
+    if size > Configuration::<u32>::MAX_SIZE {
+        return write_wide_counter_example(size, value, &options.print, w);
+    }
+
+    let value = value as u32;
+    let mut x = Configuration::new(value, size);
+    writeln!(w, "Error in the following example (size {size}, value {:#X}) :", x.value_code())?;
+
+    if let Some(path) = &options.corpus {
+        append_to_corpus(path, size, value)?;
+    }
+    if let Some(dir) = &options.dump_images {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("size_{size}_value_{value}.png"));
+        let mut file = io::BufWriter::new(fs::File::create(&path)?);
+        write_execution_png(&mut Configuration::new(value, size), expected_convergence_bound(size), &mut file)?;
+        file.flush()?;
+        writeln!(w, "diagram saved to {}", path.display())?;
+    }
+
+    let mut run = x;
+    let bit = |b : Option<bool>, none : &str| b.map_or(none.to_string(), |b| (b as u8).to_string());
+    writeln!(w, "density={}/{size} ({:.3})", x.value_code().count_ones(), x.density())?;
+    writeln!(w, "true majority={}", bit(x.majority(TiePolicy::Undefined), "tie"))?;
+    writeln!(w, "automaton result={}", bit(Sequential::converged_value(&mut run), "did not converge"))?;
+    writeln!(w, "canonical={:#X}", canonical_form(value, size))?;
+    writeln!(w, "orbit_size={}", orbit_size(value, size))?;
+
+    if Configuration::new(value, size).check() == Verdict::DidNotConverge {
+        // the execution may never end, so only its beginning is shown
+        let bound = expected_convergence_bound(size);
+        writeln!(w, "did not converge within {bound} updates")?;
+        for _ in 0..=bound {
+            x.write(w)?;
+            x.update();
+        }
+    }
+    else {
+        writeln!(w, "converged to the wrong density value")?;
+        write_execution(&mut x, &options.print, w)?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Writes a counter example of a size above Configuration::MAX_SIZE : its
+ * density, its majority, and its execution, of which only the beginning
+ * is shown if it does not converge.
+ */
+#[cfg(feature = "search")]
+fn write_wide_counter_example(size : u32, value : u64, options : &PrintOptions, w : &mut dyn Write)
+    -> io::Result<()> {
+    let mut x = Configuration64::new(value, size);
+    writeln!(w, "Error in the following example (size {size}, value {value:#X}) :")?;
+    writeln!(w, "density={}/{size}", value.count_ones())?;
+    writeln!(w, "true majority={}", x.majority(TiePolicy::Undefined).map_or("tie".to_string(), |b| (b as u8).to_string()))?;
+
+    if Configuration64::new(value, size).check() == Verdict::DidNotConverge {
+        let bound = expected_convergence_bound(size);
+        writeln!(w, "did not converge within {bound} updates")?;
+        for _ in 0..=bound {
+            x.write(w)?;
+            x.update();
+        }
+    }
+    else {
+        writeln!(w, "converged to the wrong density value")?;
+        write_execution(&mut x, options, w)?;
+    }
+
+    Ok(())
+}
+
+/**
+ * Returns true if no configuration of the given size is a counter example.
+ * This is the silent version of search_size.
+ */
+#[cfg(feature = "search")]
+pub fn size_is_clean(size : u32) -> bool {
+    find_counter_example(size, &SearchOptions::default()).is_none()
+}
+
+/**
+ * Searches the sizes from 2 to max in increasing order, like size_is_clean
+ * but stopping at the first size which has a counter example, and returns
+ * that size along with the counter example, or None if all the sizes are
+ * clean. If the options have a deadline and it passes during the search of
+ * a size, or if the process is interrupted, returns that size as an error,
+ * since the sizes from there on were not fully searched.
+ */
+#[cfg(feature = "search")]
+pub fn first_failing_size(max : u32, options : &SearchOptions) -> Result<Option<(u32, u64)>, u32> {
+    for size in 2..=max {
+        let outcome = search_configurations(size, options);
+        if let Some(value) = outcome.counter_example {
+            return Ok(Some((size, value)));
+        }
+        if outcome.timed_out || outcome.interrupted {
+            return Err(size);
+        }
+    }
+
+    Ok(None)
+}
+
+/**
+ * This function calls search_size for all sizes from 2 to 30, 30 included,
+ * and ends with a line summing up the whole search. Returns how the
+ * search ended : CounterExample if any size has one, even if a larger
+ * size was then left incomplete, and Clean only if all the sizes are.
+ * Expensive! With a checkpoint file in the options, the progress is saved
+ * along the way, see search_size_from, and with a checkpoint to resume,
+ * the sizes it says are clean are skipped.
+ */
+#[cfg(feature = "search")]
+pub fn search_all(options : &SearchOptions, w : &mut dyn Write) -> io::Result<SearchStatus> {
+    let sizes = 2..=30;
+    let start = std::time::Instant::now();
+    let mut failures = vec![];
+    let mut reduced = false;
+    let json = options.output == OutputFormat::Json;
+    // the last line of the json output, after the reports of the sizes
+    let summary = |w : &mut dyn Write, result : &str, size : Option<u32>, failures : &[u32]| writeln!(w,
+        "{{\"result\":\"{result}\",\"size\":{},\"failures\":{failures:?},\"seconds\":{:.3}}}",
+        size.map_or("null".to_string(), |size| size.to_string()), start.elapsed().as_secs_f64());
+
+    for size in sizes.clone() {
+        if options.resume.as_ref().is_some_and(|resume| resume.size > size) {
+            if json {
+                writeln!(w, "{{\"size\":{size},\"result\":\"checkpoint\"}}")?;
+            }
+            else {
+                writeln!(w, "size {size} clean (checkpoint)")?;
+            }
+            continue;
+        }
+        match search_size(size, options, w)? {
+            SearchStatus::Clean => (),
+            SearchStatus::Reduced => reduced = true,
+            SearchStatus::CounterExample => failures.push(size),
+            SearchStatus::Incomplete => {
+                let (reason, result) = if interrupted() { ("interrupted", "interrupted") }
+                    else { ("time limit reached", "time_limit") };
+                if json {
+                    summary(w, result, Some(size), &failures)?;
+                }
+                else {
+                    writeln!(w, "INCOMPLETE : {reason} during size {size}, the larger sizes were not checked")?;
+                    if ! failures.is_empty() {
+                        writeln!(w, "FAILURES at sizes {failures:?}")?;
+                    }
+                }
+                return Ok(if failures.is_empty() { SearchStatus::Incomplete } else { SearchStatus::CounterExample });
+            },
+        }
+    }
+
+    if json {
+        let result = if ! failures.is_empty() { "failures" } else if reduced { "clean_reduced" } else { "clean" };
+        summary(w, result, None, &failures)?;
+    }
+    else if failures.is_empty() {
+        let (checked, configurations) : (String, u64) = match &options.shard {
+            Some(shard) => (format!("shard {}/{} of sizes", shard.index, shard.count),
+                sizes.clone().map(|size| search_range(size, options)).map(|range| range.end - range.start).sum()),
+            None => ("sizes".to_string(), sizes.clone().map(|size| 1u64 << (size - 1)).sum()),
+        };
+        let note = if reduced { ", reduced, not exhaustive" } else { "" };
+        writeln!(w, "Checked {checked} {}..{}: all clean ({configurations} configurations{note}, {:.1?} elapsed)",
+            sizes.start(), sizes.end(), start.elapsed())?;
+    }
+    else {
+        writeln!(w, "FAILURES at sizes {failures:?}")?;
+    }
+
+    Ok(if ! failures.is_empty() { SearchStatus::CounterExample }
+        else if reduced { SearchStatus::Reduced }
+        else { SearchStatus::Clean })
+}
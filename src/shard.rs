@@ -0,0 +1,279 @@
+/*
+ * The shards splitting the search of a size into disjoint slices, to run
+ * on several machines, the files in which each shard writes its result,
+ * and their merge into one verdict for the size.
+ */
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "search")]
+use crate::{search_length, search_order, search_range, SearchOptions};
+
+/**
+ * One of count slices of the configurations of each size, so a search can
+ * be split across several machines : shard index out of count, from 1 to
+ * count, visits the configurations whose index in the order of the search
+ * is within its range. The slices of the same count are disjoint, and
+ * together they cover all the configurations. Each size searched writes
+ * its result to a file of the results directory, see write_shard_result.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shard {
+    pub index : u64,
+    pub count : u64,
+    pub results : PathBuf,
+}
+
+impl Shard {
+    /**
+     * Returns the indices of the slice of this shard, among the given
+     * number of configurations. The slices differ by at most one
+     * configuration.
+     */
+    pub fn range(&self, length : u64) -> Range<u64> {
+        let bound = |index : u64| (length as u128 * index as u128 / self.count as u128) as u64;
+        bound(self.index - 1)..bound(self.index)
+    }
+
+    /**
+     * Returns the file the result of the search of a size is written to.
+     */
+    pub fn result_path(&self, size : u32) -> PathBuf {
+        self.results.join(format!("size_{size}_shard_{}_of_{}.txt", self.index, self.count))
+    }
+}
+
+/**
+ * The result of the search of a size by a shard, read from the file
+ * written by write_shard_result, see write_merge.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShardResult {
+    pub size : u32,
+    // the shard, index out of count
+    pub index : u64,
+    pub count : u64,
+    // the order of the search, without the shard, see search_order
+    pub order : String,
+    // the slice of the shard, among the length configurations of the size
+    pub indices : Range<u64>,
+    pub length : u64,
+    // how many configurations of the slice were tested
+    pub tested : u64,
+    // clean, counter example 0x..., interrupted or time limit reached
+    pub result : String,
+}
+
+impl ShardResult {
+    /**
+     * Reads the result of a shard from a file written by
+     * write_shard_result.
+     */
+    pub fn read(path : impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let invalid = |message : String| io::Error::new(io::ErrorKind::InvalidData,
+            format!("{} : {message}", path.display()));
+
+        let text = fs::read_to_string(path)?;
+        let fields : HashMap<&str, &str> = text.lines().filter_map(|line| line.split_once(' ')).collect();
+        let field = |name : &str| fields.get(name).copied().ok_or_else(|| invalid(format!("no {name}")));
+        let number = |text : &str| text.trim().parse::<u64>().map_err(|_| invalid(format!("invalid number {text:?}")));
+        let pair = |name : &str, separator : &str| field(name).and_then(|text| text.split_once(separator)
+            .ok_or_else(|| invalid(format!("invalid {name} {text:?}"))));
+
+        let size = number(field("size")?)? as u32;
+        let (index, count) = pair("shard", "/")?;
+        let (index, count) = (number(index)?, number(count)?);
+        let (indices, length) = pair("indices", " of ")?;
+        let (start, end) = indices.split_once("..").ok_or_else(|| invalid(format!("invalid indices {indices:?}")))?;
+        let (tested, _) = pair("tested", " of ")?;
+        let order = field("order")?;
+
+        Ok(ShardResult {
+            size,
+            index,
+            count,
+            order : order.strip_suffix(&format!(" shard {index}/{count}")).unwrap_or(order).to_string(),
+            indices : number(start)?..number(end)?,
+            length : number(length)?,
+            tested : number(tested)?,
+            result : field("result")?.to_string(),
+        })
+    }
+
+    /**
+     * Returns true if the whole slice of the shard is clean.
+     */
+    pub fn is_clean(&self) -> bool {
+        self.result == "clean"
+    }
+
+    /**
+     * Returns the counter example the shard found, if any.
+     */
+    pub fn counter_example(&self) -> Option<u64> {
+        let hex = self.result.strip_prefix("counter example 0x")?;
+        u64::from_str_radix(hex, 16).ok()
+    }
+}
+
+/**
+ * Reads the results of the shards from the given files, and from the
+ * result files of the given directories, see Shard::result_path.
+ */
+pub fn read_shard_results(paths : &[PathBuf]) -> io::Result<Vec<ShardResult>> {
+    let mut results = vec![];
+
+    for path in paths {
+        if ! path.is_dir() {
+            results.push(ShardResult::read(path)?);
+            continue;
+        }
+
+        let mut files = vec![];
+        for entry in fs::read_dir(path)? {
+            let file = entry?.path();
+            let name = file.file_name().and_then(|name| name.to_str()).unwrap_or("");
+            if name.starts_with("size_") && name.contains("_shard_") && name.ends_with(".txt") {
+                files.push(file);
+            }
+        }
+        files.sort();
+        for file in files {
+            results.push(ShardResult::read(file)?);
+        }
+    }
+
+    Ok(results)
+}
+
+/**
+ * The combined verdict of the results of shards, see write_merge.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeVerdict {
+    // every size is covered by clean slices
+    Clean,
+    // a shard found a counter example, whether the sizes are covered or not
+    CounterExamples,
+    // no counter example, but some configurations were not covered by a
+    // clean slice
+    Gaps,
+}
+
+/**
+ * Returns the ranges of the indices below length which none of the given
+ * slices covers.
+ */
+fn coverage_gaps(mut slices : Vec<Range<u64>>, length : u64) -> Vec<Range<u64>> {
+    slices.sort_by_key(|slice| slice.start);
+
+    let mut gaps = vec![];
+    let mut covered = 0;
+    for slice in slices {
+        if slice.start > covered {
+            gaps.push(covered..slice.start);
+        }
+        covered = covered.max(slice.end);
+    }
+    if covered < length {
+        gaps.push(covered..length);
+    }
+
+    gaps
+}
+
+/**
+ * Merges the results of shards, size by size : writes the counter
+ * examples they found, and checks that the clean slices cover all the
+ * configurations of the size, listing the indices left out otherwise.
+ * The indices only mean something in one order, so the shards of a size
+ * must all have been searched in the same order. Ends with the combined
+ * verdict, which is returned.
+ */
+pub fn write_merge(results : &[ShardResult], w : &mut dyn Write) -> io::Result<MergeVerdict> {
+    let mut sizes : BTreeMap<u32, Vec<&ShardResult>> = BTreeMap::new();
+    for result in results {
+        sizes.entry(result.size).or_default().push(result);
+    }
+
+    let (mut failures, mut gaps) = (vec![], vec![]);
+    for (&size, results) in &sizes {
+        let first = results[0];
+        if results.iter().any(|result| result.order != first.order || result.length != first.length) {
+            writeln!(w, "size {size} : the shards were searched in different orders, they cannot be merged")?;
+            gaps.push(size);
+            continue;
+        }
+
+        for result in results {
+            if let Some(value) = result.counter_example() {
+                writeln!(w, "size {size} : counter example {value:#X} (shard {}/{})", result.index, result.count)?;
+            }
+        }
+        if results.iter().any(|result| result.counter_example().is_some()) {
+            failures.push(size);
+        }
+
+        let clean = results.iter().filter(|result| result.is_clean()).map(|result| result.indices.clone());
+        let missing = coverage_gaps(clean.collect(), first.length);
+        if missing.is_empty() {
+            writeln!(w, "size {size} clean ({} shards, {} configurations)", results.len(), first.length)?;
+        }
+        else {
+            let count : u64 = missing.iter().map(|gap| gap.end - gap.start).sum();
+            let listed : Vec<String> = missing.iter().take(10).map(|gap| format!("{}..{}", gap.start, gap.end)).collect();
+            writeln!(w, "size {size} : {count} of {} configurations not covered by a clean shard, at indices {}{}",
+                first.length, listed.join(", "), if missing.len() > 10 { ", ..." } else { "" })?;
+            gaps.push(size);
+        }
+    }
+
+    if ! failures.is_empty() {
+        writeln!(w, "COUNTER EXAMPLES at sizes {failures:?}")?;
+    }
+    if ! gaps.is_empty() {
+        writeln!(w, "GAPS REMAINING at sizes {gaps:?}")?;
+    }
+
+    if ! failures.is_empty() {
+        Ok(MergeVerdict::CounterExamples)
+    }
+    else if ! gaps.is_empty() {
+        Ok(MergeVerdict::Gaps)
+    }
+    else {
+        writeln!(w, "Merged {} shard results : sizes {:?} all clean", results.len(), sizes.keys().collect::<Vec<_>>())?;
+        Ok(MergeVerdict::Clean)
+    }
+}
+
+/**
+ * Writes the result of the search of a size to the result file of the
+ * shard of the options, if any : the slice searched, how many of its
+ * configurations were tested, and the outcome, clean, counter example
+ * 0x..., interrupted or time limit reached. Only a clean result covers
+ * the whole slice.
+ */
+#[cfg(feature = "search")]
+pub(crate) fn write_shard_result(size : u32, options : &SearchOptions, tested : u64, total : u64, result : &str)
+    -> io::Result<()> {
+    let Some(shard) = &options.shard else {
+        return Ok(());
+    };
+    let range = search_range(size, options);
+
+    fs::create_dir_all(&shard.results)?;
+    let mut file = fs::File::create(shard.result_path(size))?;
+    writeln!(file, "size {size}")?;
+    writeln!(file, "shard {}/{}", shard.index, shard.count)?;
+    writeln!(file, "order {}", search_order(options))?;
+    writeln!(file, "indices {}..{} of {}", range.start, range.start + total, search_length(size, options).unwrap_or(0))?;
+    writeln!(file, "tested {tested} of {total}")?;
+    writeln!(file, "result {result}")?;
+    file.sync_all()
+}
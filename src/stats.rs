@@ -0,0 +1,177 @@
+/*
+ * The confidence intervals of the sampled success rates : the Wilson score
+ * interval, and the exact Clopper-Pearson interval, which needs the
+ * quantiles of the beta distribution. These are computed from the
+ * regularized incomplete beta function, with its continued fraction, and
+ * found by bisection : slow, but only computed once per size. The counts
+ * are real numbers, so that the effective sizes of stratified samples can
+ * be given too.
+ */
+
+/**
+ * The two-sided confidence intervals a sampled success rate can be given
+ * with, both at 95%.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Interval {
+    // The Wilson score interval : close to the nominal 95% on average, and
+    // unlike the usual interval around the estimate, it does not collapse
+    // to a single point when no failure was found.
+    #[default]
+    Wilson,
+    // The Clopper-Pearson interval, from the exact binomial distribution :
+    // it is never below 95%, at the price of being wider.
+    ClopperPearson,
+}
+
+impl Interval {
+    /**
+     * Returns the interval of this kind of the success rate, given the
+     * number of successes among the tested configurations.
+     */
+    pub fn bounds(self, successes : f64, tested : f64) -> (f64, f64) {
+        match self {
+            Interval::Wilson => wilson(successes, tested),
+            Interval::ClopperPearson => clopper_pearson(successes, tested),
+        }
+    }
+}
+
+// the quantile of the normal distribution for a two-sided 95% interval
+const Z : f64 = 1.96;
+
+// the share left out of the interval on each side
+const ALPHA_2 : f64 = 0.025;
+
+/**
+ * Returns the Wilson score interval of the success rate, given the number
+ * of successes among the tested configurations.
+ */
+pub fn wilson(successes : f64, tested : f64) -> (f64, f64) {
+    if tested <= 0.0 {
+        return (0.0, 1.0);
+    }
+
+    let n = tested;
+    let p = successes / n;
+    let center = p + Z * Z / (2.0 * n);
+    let spread = Z * (p * (1.0 - p) / n + Z * Z / (4.0 * n * n)).sqrt();
+    let scale = 1.0 + Z * Z / n;
+
+    (((center - spread) / scale).max(0.0), ((center + spread) / scale).min(1.0))
+}
+
+/**
+ * Returns the Clopper-Pearson interval of the success rate, given the
+ * number of successes among the tested configurations : the rates under
+ * which seeing at least, resp. at most, that many successes has a
+ * probability of ALPHA_2, which are quantiles of beta distributions.
+ */
+pub fn clopper_pearson(successes : f64, tested : f64) -> (f64, f64) {
+    if tested <= 0.0 {
+        return (0.0, 1.0);
+    }
+
+    let (x, n) = (successes, tested);
+    let low = if x <= 0.0 { 0.0 } else { beta_quantile(x, n - x + 1.0, ALPHA_2) };
+    let high = if x >= n { 1.0 } else { beta_quantile(x + 1.0, n - x, 1.0 - ALPHA_2) };
+
+    (low, high)
+}
+
+/**
+ * Returns the q quantile of the beta distribution of parameters a and b,
+ * by bisection on its cumulative distribution function.
+ */
+fn beta_quantile(a : f64, b : f64, q : f64) -> f64 {
+    let (mut low, mut high) = (0.0, 1.0);
+
+    for _ in 0..100 {
+        let middle = (low + high) / 2.0;
+        if incomplete_beta(a, b, middle) < q { low = middle; } else { high = middle; }
+    }
+
+    (low + high) / 2.0
+}
+
+/**
+ * The regularized incomplete beta function I_x(a, b), the cumulative
+ * distribution function of the beta distribution, from its continued
+ * fraction. The fraction converges quickly for x below (a + 1) / (a + b + 2),
+ * and the symmetry I_x(a, b) = 1 - I_(1 - x)(b, a) covers the other side.
+ */
+pub fn incomplete_beta(a : f64, b : f64, x : f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+
+    let log_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+
+    if x < (a + 1.0) / (a + b + 2.0) {
+        log_front.exp() * beta_continued_fraction(a, b, x) / a
+    }
+    else {
+        1.0 - log_front.exp() * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+/**
+ * Evaluates the continued fraction of the incomplete beta function with
+ * the modified Lentz method.
+ */
+fn beta_continued_fraction(a : f64, b : f64, x : f64) -> f64 {
+    const TINY : f64 = 1e-300;
+    let clamp = |v : f64| if v.abs() < TINY { TINY } else { v };
+
+    let mut c = 1.0;
+    let mut d = 1.0 / clamp(1.0 - (a + b) * x / (a + 1.0));
+    let mut fraction = d;
+
+    for m in 1..1000 {
+        let m = m as f64;
+
+        // the even step, then the odd one
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 / clamp(1.0 + even * d);
+        c = clamp(1.0 + even / c);
+        fraction *= d * c;
+
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 / clamp(1.0 + odd * d);
+        c = clamp(1.0 + odd / c);
+        let delta = d * c;
+        fraction *= delta;
+
+        if (delta - 1.0).abs() < 1e-15 {
+            break;
+        }
+    }
+
+    fraction
+}
+
+/**
+ * The logarithm of the gamma function, with the Lanczos approximation
+ * (g = 7, 9 coefficients), precise to about 15 digits for x > 0.
+ */
+fn ln_gamma(x : f64) -> f64 {
+    const COEFFICIENTS : [f64; 9] = [
+        0.999_999_999_999_809_9, 676.520_368_121_885_1, -1_259.139_216_722_402_8,
+        771.323_428_777_653_1, -176.615_029_162_140_6, 12.507_343_278_686_905,
+        -0.138_571_095_265_720_12, 9.984_369_578_019_572e-6, 1.505_632_735_149_311_6e-7,
+    ];
+
+    if x < 0.5 {
+        // reflection formula, for the small arguments
+        return (std::f64::consts::PI / (std::f64::consts::PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = COEFFICIENTS[0] + (1..9).map(|k| COEFFICIENTS[k] / (x + k as f64)).sum::<f64>();
+
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
@@ -0,0 +1,441 @@
+/*
+ * The symmetries of the ring : the rotations, mirror images and complements
+ * of the configurations, their orbits and canonical forms, the necklaces
+ * enumerating them, and the checks that the rule respects them, which the
+ * reductions of the searches rely on.
+ */
+
+use std::collections::HashSet;
+use std::io::{self, Write};
+
+use crate::{
+    expected_convergence_bound, local_branch, local_state, local_step, CellState, Configuration,
+    RuleBranch, Verdict,
+};
+
+/**
+ * Returns the indices of the 1 bits of a number, in increasing order.
+ */
+pub(crate) fn bit_indices(mut bits : u32) -> Vec<u32> {
+    let mut indices = Vec::with_capacity(bits.count_ones() as usize);
+
+    while bits != 0 {
+        indices.push(bits.trailing_zeros());
+        bits &= bits - 1; // clears the lowest bit
+    }
+
+    indices
+}
+
+/**
+ * Rotates the cells of a value of the given size by shift cells towards
+ * the higher indices, on the ring : cell k goes to cell (k + shift) % size.
+ * The bits beyond the size are dropped. The size must be between 1 and 32.
+ */
+pub fn rotate(value : u32, size : u32, shift : u32) -> u32 {
+    let mask = (1u64 << size) - 1;
+    let value = value as u64 & mask;
+    let shift = shift % size;
+
+    ((value << shift | value >> (size - shift)) & mask) as u32
+}
+
+/**
+ * Returns the mirror image of a value of the given size : cell k goes to
+ * cell size - 1 - k. The bits beyond the size are dropped. The size must
+ * be at most 32.
+ */
+pub fn reflect(value : u32, size : u32) -> u32 {
+    // a shift by 64 would overflow
+    (value as u64).reverse_bits().checked_shr(64 - size).unwrap_or(0) as u32
+}
+
+/**
+ * Swaps the 0s and 1s of a value of the given size. The bits beyond the
+ * size are dropped. The size must be at most 32.
+ */
+pub fn complement(value : u32, size : u32) -> u32 {
+    ! value & ((1u64 << size) - 1) as u32
+}
+
+/**
+ * Returns true if the two values of the given size are rotations of each
+ * other, or, when with_complement is set, if one is a rotation of the
+ * other or of its complement. The rule treats these configurations the
+ * same way, so this groups them into equivalence classes.
+ */
+pub fn same_orbit(a : u32, b : u32, size : u32, with_complement : bool) -> bool {
+    let b = rotate(b, size, 0);
+    (0..size).any(|shift| {
+        let rotated = rotate(a, size, shift);
+        rotated == b || (with_complement && complement(rotated, size) == b)
+    })
+}
+
+/**
+ * Returns the smallest value among all the rotations of a value and of
+ * its complement : the representative of its class under the group
+ * generated by the rotations and the complement, which has 2 * size
+ * elements (a rotation, then maybe a complement). Mirror images are not
+ * part of the group, since the sweep goes from left to right and the rule
+ * is not symmetric under reflection. Two values have the same canonical
+ * form exactly when same_orbit with complements holds for them.
+ */
+pub fn canonical_form(value : u32, size : u32) -> u32 {
+    (0..size)
+        .map(|shift| rotate(value, size, shift))
+        .flat_map(|rotated| [rotated, complement(rotated, size)])
+        .min()
+        .unwrap_or(0)
+}
+
+/**
+ * Returns the number of distinct configurations in the class of a value
+ * under rotations and complement, the group used by canonical_form. It
+ * divides 2 * size.
+ */
+pub fn orbit_size(value : u32, size : u32) -> u32 {
+    let members : HashSet<u32> = (0..size)
+        .map(|shift| rotate(value, size, shift))
+        .flat_map(|rotated| [rotated, complement(rotated, size)])
+        .collect();
+    members.len() as u32
+}
+
+/**
+ * Iterates in increasing order over the binary necklaces of a given size :
+ * the values which are the smallest of their rotations, one for each class
+ * of rotations. The canonical forms are the necklaces whose complement has
+ * no smaller rotation.
+ *
+ * This is the algorithm of Fredricksen, Kessler and Maiorana, reading the
+ * cells from the highest index down as a word : the next prenecklace is
+ * found by increasing the last 0 of the word to a 1 and repeating the
+ * prefix it ends over the rest of the word, and it is a necklace when the
+ * length of that prefix divides the size. This takes constant amortized
+ * time per necklace.
+ */
+pub struct Necklaces {
+    size : u32,
+    next : Option<u64>,
+}
+
+impl Necklaces {
+    pub fn new(size : u32) -> Self {
+        assert!(size <= Configuration::<u32>::MAX_SIZE, "no necklaces of size {size}");
+        Self { size, next : Some(0) }
+    }
+}
+
+impl Iterator for Necklaces {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        let current = self.next?;
+
+        let mut word = current;
+        self.next = loop {
+            // the last 0 of the word is its lowest 0 bit
+            let last_zero = word.trailing_ones();
+            if last_zero >= self.size {
+                break None; // only 1s left, this was the last necklace
+            }
+
+            let period = self.size - last_zero;
+            let prefix = word >> last_zero | 1;
+            let mut repeated = prefix;
+            let mut length = period;
+            while length < self.size {
+                repeated = repeated << period | prefix;
+                length += period;
+            }
+            word = repeated >> (length - self.size);
+
+            if self.size.is_multiple_of(period) {
+                break Some(word);
+            }
+        };
+
+        Some(current as u32)
+    }
+}
+
+/**
+ * Returns the number of classes of the configurations of a given size
+ * under rotations and complement, that is the number of canonical forms
+ * (OEIS A000013) : the sum of phi(2d) 2^(size / d) over the divisors d of
+ * the size, divided by 2 * size, where phi is Euler's totient.
+ */
+pub fn necklace_count(size : u32) -> u64 {
+    if size == 0 {
+        return 1;
+    }
+
+    let phi = |n : u64| (1..=n).filter(|&k| gcd(k, n) == 1).count() as u64;
+    let sum : u64 = (1..=size as u64)
+        .filter(|d| (size as u64).is_multiple_of(*d))
+        .map(|d| phi(2 * d) << (size as u64 / d))
+        .sum();
+
+    sum / (2 * size as u64)
+}
+
+/**
+ * Returns the smallest value among the canonical forms of a value and of
+ * its mirror image : the representative of its class under the rotations,
+ * the reflections and the complement.
+ */
+pub fn dihedral_form(value : u32, size : u32) -> u32 {
+    canonical_form(value, size).min(canonical_form(reflect(value, size), size))
+}
+
+/**
+ * Returns the number of classes of the configurations of a given size
+ * under rotations, reflections and complement, that is the number of
+ * dihedral forms (OEIS A000011). By Burnside's lemma, the reflections
+ * add to the classes of necklace_count the 2^(size / 2) configurations
+ * of each class which are their own mirror image, up to rotation and
+ * complement, and the count is halved.
+ */
+pub fn bracelet_count(size : u32) -> u64 {
+    (necklace_count(size) + (1 << (size / 2))) / 2
+}
+
+/**
+ * Returns the greatest common divisor of two numbers.
+ */
+fn gcd(a : u64, b : u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/**
+ * The verdicts of all the rotations of a configuration, as returned by
+ * check_orbit.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OrbitReport {
+    // the verdict of the configuration rotated by k cells, at index k
+    pub verdicts : Vec<Verdict>,
+}
+
+impl OrbitReport {
+    /**
+     * Returns the distinct verdicts seen across the orbit, in the order
+     * they were first seen.
+     */
+    pub fn distinct(&self) -> Vec<Verdict> {
+        let mut distinct = vec![];
+        for &verdict in &self.verdicts {
+            if ! distinct.contains(&verdict) {
+                distinct.push(verdict);
+            }
+        }
+        distinct
+    }
+
+    /**
+     * Returns true if all the rotations got the same verdict, which they
+     * should since the ring has no boundary.
+     */
+    pub fn agrees(&self) -> bool {
+        self.distinct().len() <= 1
+    }
+}
+
+/**
+ * Runs check on every rotation of a configuration. Rotations are the same
+ * configuration up to where the ring starts, so a disagreement between
+ * their verdicts reveals a bug in how the rule handles index 0.
+ */
+pub fn check_orbit(value : u32, size : u32) -> OrbitReport {
+    let verdicts = (0..size)
+        .map(|shift| Configuration::new(rotate(value, size, shift), size).check())
+        .collect();
+
+    OrbitReport { verdicts }
+}
+
+/**
+ * Returns the first configuration of the given size whose rotations do not
+ * all get the same verdict, if any.
+ */
+pub(crate) fn check_rotation_symmetry(size : u32) -> Option<u32> {
+    (0..1 << size).find(|&value| ! check_orbit(value, size).agrees())
+}
+
+/**
+ * Returns true if check_rotation_symmetry holds on all the sizes up to
+ * REFLECTION_CHECK_SIZE, which the reductions by rotation of the searches
+ * rely on. This is only evidence for the larger sizes, see
+ * Reduction::Rotation. Computed once, in a few tens of milliseconds.
+ */
+pub fn rotation_compatible() -> bool {
+    static COMPATIBLE : std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *COMPATIBLE.get_or_init(|| (1..=REFLECTION_CHECK_SIZE).all(|size| check_rotation_symmetry(size).is_none()))
+}
+
+/**
+ * Returns the first configuration of the given size whose mirror image
+ * does not converge in the same number of updates, or not to the same
+ * value, if any. Ties are compared too, although they are always
+ * correct, as they are the only executions whose value is not fixed by
+ * the rule being correct.
+ */
+pub(crate) fn check_reflection_symmetry(size : u32) -> Option<u32> {
+    let run = |value| {
+        let mut x = Configuration::new(value, size);
+        let (converged, remaining) = x.run_with_budget(expected_convergence_bound(size));
+        (converged, remaining, x.value)
+    };
+
+    (0..1 << size).find(|&value| run(value) != run(reflect(value, size)))
+}
+
+/**
+ * Returns the swapped 0s and 1s of a configuration, see
+ * CellState::complement, in all its layers.
+ */
+fn complement_layers(x : Configuration) -> Configuration {
+    Configuration {
+        value : complement(x.value, x.size),
+        mem_0 : x.mem_1,
+        mem_1 : x.mem_0,
+        ..x
+    }
+}
+
+/**
+ * Returns the pairs of states of a cell and of its left neighbor, among
+ * all the 64 states of each, on which local_step does not commute with
+ * CellState::complement. There are some, so the rule is not symmetric
+ * between 0 and 1 : an intermediate cell reverting next to a cell of the
+ * same color whose memory is empty defaults to 0. That only ties reach
+ * these failures is checked up to REFLECTION_CHECK_SIZE by
+ * check_complement_symmetry, and not proven above.
+ */
+pub fn complement_asymmetries() -> Vec<(CellState, CellState)> {
+    (0..64 * 64)
+        .map(|bits| (local_state(bits / 64), local_state(bits % 64)))
+        .filter(|&(left, current)| {
+            local_step(left.complement(), current.complement()) != local_step(left, current).complement()
+        })
+        .collect()
+}
+
+/**
+ * Returns true if the pair of states is one of the failures which
+ * complement_asymmetries expects : the cell reverts to boolean while the
+ * memory of its left neighbor holds nothing.
+ */
+fn is_failure_revert(left : CellState, current : CellState) -> bool {
+    local_branch(left, current) == RuleBranch::RevertToZero && ! left.mem_0 && ! left.mem_1
+}
+
+/**
+ * Returns the first configuration of the given size, among those which are
+ * not ties, whose complement does not run as its complement at every step
+ * up to the convergence bound, if any.
+ */
+pub(crate) fn check_complement_symmetry(size : u32) -> Option<u32> {
+    (0..1u32 << size)
+        .filter(|&value| 2 * value.count_ones() != size)
+        .find(|&value| {
+            let mut x = Configuration::new(value, size);
+            let mut y = Configuration::new(complement(value, size), size);
+
+            (0..=expected_convergence_bound(size)).any(|_| {
+                let differs = y != complement_layers(x);
+                x.update();
+                y.update();
+                differs
+            })
+        })
+}
+
+/**
+ * Returns true if the only neighborhoods on which the rule is not
+ * symmetric between 0 and 1 are the failures of complement_asymmetries,
+ * and if check_complement_symmetry holds on all the sizes up to
+ * REFLECTION_CHECK_SIZE. The searches only test the configurations whose
+ * last cell is a 0 when this holds, by default : above
+ * REFLECTION_CHECK_SIZE, this halving is an unproven assumption, and only
+ * --reduce full (Reduction::Full) is an exhaustive proof.
+ * Computed once, in a few milliseconds.
+ */
+pub fn complement_compatible() -> bool {
+    static COMPATIBLE : std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *COMPATIBLE.get_or_init(|| {
+        complement_asymmetries().into_iter().all(|(left, current)| is_failure_revert(left, current)) &&
+            (1..=REFLECTION_CHECK_SIZE).all(|size| check_complement_symmetry(size).is_none())
+    })
+}
+
+/**
+ * Writes the neighborhoods on which the rule is not symmetric between 0
+ * and 1, see complement_asymmetries, and checks the executions of the
+ * small sizes against their complements. Returns true if
+ * complement_compatible holds, that is if searching half of the
+ * configurations is enough.
+ */
+pub fn write_complement_symmetry(w : &mut dyn Write) -> io::Result<bool> {
+    let asymmetries = complement_asymmetries();
+    writeln!(w, "{} of the {} neighborhoods are not symmetric :", asymmetries.len(), 64 * 64)?;
+
+    let mut symmetric = true;
+    for &(left, current) in &asymmetries {
+        let failure = is_failure_revert(left, current);
+        symmetric &= failure;
+        writeln!(w, "{} {} -> {} {}", left.describe(), current.describe(),
+            local_step(left, current).describe(), if failure { "failure" } else { "unexpected" })?;
+    }
+
+    for size in 1..=REFLECTION_CHECK_SIZE {
+        if let Some(value) = check_complement_symmetry(size) {
+            writeln!(w, "size {size} : the complement of {value:#X} runs differently")?;
+            symmetric = false;
+        }
+    }
+
+    if symmetric {
+        writeln!(w, "the rule is symmetric between 0 and 1 except on failures, \
+            which only ties reach up to size {REFLECTION_CHECK_SIZE} ; above it, searching half of the \
+            configurations is unproven, and only --reduce full is exhaustive")?;
+    }
+
+    Ok(symmetric)
+}
+
+/**
+ * The largest size up to which reflection_compatible, rotation_compatible
+ * and complement_compatible check the executions against their mirror
+ * images, rotations and complements. The reductions relying on them are
+ * unproven above it.
+ */
+pub const REFLECTION_CHECK_SIZE : u32 = 12;
+
+/**
+ * Returns true if check_reflection_symmetry holds on all the sizes up to
+ * REFLECTION_CHECK_SIZE, which the reflection reduction of the searches
+ * relies on : a heuristic for the larger sizes, not a proof, see
+ * Reduction::Reflection. Computed once, in a few milliseconds.
+ */
+pub fn reflection_compatible() -> bool {
+    static COMPATIBLE : std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *COMPATIBLE.get_or_init(|| (1..=REFLECTION_CHECK_SIZE).all(|size| check_reflection_symmetry(size).is_none()))
+}
+
+/**
+ * Writes the verdict of each rotation of a configuration, and returns true
+ * if they all agree.
+ */
+pub fn write_orbit(x : &Configuration, w : &mut dyn Write) -> io::Result<bool> {
+    let report = check_orbit(x.value, x.size);
+
+    for (shift, verdict) in report.verdicts.iter().enumerate() {
+        let rotated = Configuration::new(rotate(x.value, x.size, shift as u32), x.size);
+        writeln!(w, "{shift:3} {} {verdict:?}", rotated.value_string())?;
+    }
+    writeln!(w, "distinct verdicts : {:?}", report.distinct())?;
+
+    Ok(report.agrees())
+}
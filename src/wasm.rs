@@ -0,0 +1,84 @@
+/*
+ * A minimal interface to run single executions from WebAssembly, for
+ * instance to animate them in a browser. Only plain numbers go through
+ * it, so it needs no binding generator. The module is part of the
+ * library, built as a cdylib to get the WebAssembly module :
+ *
+ *     cargo rustc --release --lib --no-default-features --crate-type cdylib --target wasm32-unknown-unknown
+ *
+ * and drive it from JavaScript :
+ *
+ *     const { instance } = await WebAssembly.instantiateStreaming(fetch("density_checker.wasm"));
+ *     const dc = instance.exports;
+ *     dc.reset(0b0110100, 7);
+ *     do {
+ *         draw(dc.layer(0), dc.layer(1), dc.layer(2), dc.layer(3), dc.layer(4), dc.layer(5));
+ *     } while (! dc.step());
+ *
+ * There is a single execution at a time, kept by this module.
+ */
+
+use std::sync::Mutex;
+
+use crate::Configuration;
+
+static STATE : Mutex<Configuration> = Mutex::new(Configuration {
+    size : 0, value : 0, alphabet : 0, taken : 0, color : 0, mem_0 : 0, mem_1 : 0,
+});
+
+/**
+ * Starts a new execution from the given initial configuration. Sizes above
+ * 31 are reduced to 31, and bits of value beyond the size are ignored.
+ */
+#[no_mangle]
+pub extern "C" fn reset(value : u32, size : u32) {
+    let size = size.clamp(1, Configuration::<u32>::MAX_SIZE);
+    *STATE.lock().unwrap() = Configuration::new(value & ((1 << size) - 1), size);
+}
+
+/**
+ * Applies one update to the current execution, unless it already
+ * converged. Returns true if the configuration has converged.
+ */
+#[no_mangle]
+pub extern "C" fn step() -> bool {
+    let mut x = STATE.lock().unwrap();
+    if ! x.has_converged() {
+        x.update();
+    }
+    x.has_converged()
+}
+
+/**
+ * Runs the execution started by reset until it converges, or until cap
+ * updates were applied. Returns the number of updates applied.
+ */
+#[no_mangle]
+pub extern "C" fn run_trace(cap : u32) -> u32 {
+    let mut x = STATE.lock().unwrap();
+    let mut steps = 0;
+    while ! x.has_converged() && steps < cap {
+        x.update();
+        steps += 1;
+    }
+    steps
+}
+
+/**
+ * Returns one layer of the current configuration, bit k being the cell of
+ * index k : 0 for value, 1 for alphabet, 2 for taken, 3 for color, 4 for
+ * mem_0 and 5 for mem_1. Any other index returns the size.
+ */
+#[no_mangle]
+pub extern "C" fn layer(index : u32) -> u32 {
+    let x = STATE.lock().unwrap();
+    match index {
+        0 => x.value,
+        1 => x.alphabet,
+        2 => x.taken,
+        3 => x.color,
+        4 => x.mem_0,
+        5 => x.mem_1,
+        _ => x.size,
+    }
+}
@@ -0,0 +1,50 @@
+/*
+ * The numbers the layers of a configuration can be made of : u32 for the
+ * sizes up to 31, which is what the searches run on, and u64 and u128 for
+ * the sizes up to 63 and 127. LargeConfiguration takes over beyond them.
+ */
+
+use std::fmt::{Debug, UpperHex};
+use std::hash::Hash;
+use std::ops::{BitAndAssign, BitOrAssign, Sub};
+
+use crate::bits::Bits;
+use crate::Configuration;
+
+/**
+ * The unsigned numbers a configuration can be backed by. Configuration is
+ * generic over them, so the local function and the executions are written
+ * once, and compiled for each width : the u32 search path stays as fast
+ * as if it were written for u32 alone.
+ */
+pub trait Word : Bits + Sub<Output = Self> + BitAndAssign + BitOrAssign + From<bool>
+    + Eq + Hash + Debug + Default + UpperHex + Send + Sync {
+    fn count_ones(self) -> u32;
+}
+
+macro_rules! impl_word {
+    ($($t:ty),*) => {
+        $(
+            impl Word for $t {
+                #[inline]
+                fn count_ones(self) -> u32 {
+                    <$t>::count_ones(self)
+                }
+            }
+        )*
+    }
+}
+
+impl_word!(u32, u64, u128);
+
+/**
+ * A configuration with u64 layers, for the sizes up to 63.
+ */
+pub type Configuration64 = Configuration<u64>;
+
+/**
+ * A configuration with u128 layers, for the sizes up to 127. Searching all
+ * the configurations of these sizes is out of the question, this is for
+ * random or sampled experiments.
+ */
+pub type Configuration128 = Configuration<u128>;
@@ -0,0 +1,69 @@
+/*
+ * Checks the public API of the library the way a user of the crate would
+ * call it : the configurations, their executions, the symmetries of the
+ * values and the searches.
+ */
+
+use density_checker::{complement, perturbation_study, reflect, rotate, Configuration, Verdict};
+
+#[test]
+fn configurations_converge_to_their_majority() {
+    // three 1s out of seven cells
+    let mut x = Configuration::new(0b0010110u32, 7);
+
+    assert_eq!(x.majority(density_checker::TiePolicy::Undefined), Some(false));
+    assert_eq!(x.check(), Verdict::Correct);
+    assert!(x.has_converged());
+    assert_eq!(x.value, 0);
+}
+
+#[test]
+fn rotations_move_cells_around_the_ring() {
+    assert_eq!(rotate(0b00011, 5, 1), 0b00110);
+    assert_eq!(rotate(0b10001, 5, 1), 0b00011);
+    assert_eq!(rotate(0b10001, 5, 6), 0b00011);
+    assert_eq!(rotate(0b1011, 4, 0), 0b1011);
+    assert_eq!(rotate(u32::MAX, 32, 7), u32::MAX);
+}
+
+#[test]
+fn reflections_mirror_the_cells() {
+    assert_eq!(reflect(0b00011, 5), 0b11000);
+    assert_eq!(reflect(0b0110, 4), 0b0110);
+    assert_eq!(reflect(1, 32), 1 << 31);
+    assert_eq!(reflect(0, 0), 0);
+
+    for value in 0..1 << 8 {
+        assert_eq!(reflect(reflect(value, 8), 8), value);
+    }
+}
+
+#[test]
+fn complements_swap_0s_and_1s_within_the_size() {
+    assert_eq!(complement(0b00011, 5), 0b11100);
+    assert_eq!(complement(0, 32), u32::MAX);
+    assert_eq!(complement(0b11111, 5), 0);
+}
+
+#[test]
+fn single_flips_only_change_the_result_across_the_majority() {
+    // four 1s out of seven cells : flipping one of them makes a majority
+    // of 0s, flipping a 0 keeps a majority of 1s
+    let x = Configuration::new(0b1010110u32, 7);
+
+    for (k, distance) in perturbation_study(&x) {
+        let expected = if x.value & 1 << k != 0 { 7 } else { 0 };
+        assert_eq!(distance, expected, "cell {k}");
+    }
+}
+
+#[test]
+#[cfg(feature = "search")]
+fn searches_find_no_counter_example() {
+    let options = density_checker::SearchOptions::default();
+
+    for size in 2..=14 {
+        assert_eq!(density_checker::find_counter_example(size, &options), None, "size {size}");
+    }
+    assert!(density_checker::failure_histogram_by_density(12).iter().all(|&(_, failures)| failures == 0));
+}
@@ -0,0 +1,77 @@
+/*
+ * Checks the corpus of counter examples shipped with the crate, and the
+ * reading and writing of corpus files.
+ */
+
+use std::fs;
+use std::path::PathBuf;
+
+use density_checker::{append_to_corpus, read_corpus, Configuration};
+
+/**
+ * Returns a path in the temporary directory, unique to this process and
+ * to the given name, with no file at it.
+ */
+fn temporary_path(name : &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("density_checker_{}_{name}", std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+#[test]
+#[cfg(feature = "search")]
+fn shipped_corpus_still_fails() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/corpus.txt");
+    let report = density_checker::reverify_corpus(path).unwrap();
+
+    assert!(report.is_reproduced(), "now passing : {:?}", report.now_passing);
+}
+
+#[test]
+#[cfg(feature = "search")]
+fn reverify_reports_passing_entries() {
+    let path = temporary_path("passing.txt");
+    fs::write(&path, "# correct configurations\n\n0110100\n111000110\n").unwrap();
+
+    let report = density_checker::reverify_corpus(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert!(report.still_failing.is_empty());
+    assert_eq!(report.now_passing, vec![(7, 0b0010110), (9, 0b011000111)]);
+    assert!(! report.is_reproduced());
+}
+
+#[test]
+fn append_skips_duplicates() {
+    let path = temporary_path("append.txt");
+
+    assert!(append_to_corpus(&path, 5, 0b00110).unwrap());
+    assert!(append_to_corpus(&path, 6, 0b00110).unwrap());
+    assert!(! append_to_corpus(&path, 5, 0b00110).unwrap());
+    let entries = read_corpus(&path).unwrap();
+    let contents = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(entries, vec![(5, 0b00110), (6, 0b00110)]);
+    assert_eq!(contents, "01100\n011000\n");
+}
+
+#[test]
+fn read_reports_malformed_lines() {
+    let path = temporary_path("malformed.txt");
+    fs::write(&path, "0110\n01x0\n").unwrap();
+
+    let error = read_corpus(&path).unwrap_err();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    assert!(error.to_string().starts_with("line 2 :"), "{error}");
+}
+
+#[test]
+fn corpus_lines_parse_back() {
+    let x = Configuration::new(0b1011u32, 6);
+    let y : Configuration<u32> = x.value_string().parse().unwrap();
+
+    assert_eq!((y.size, y.value), (6, 0b1011));
+}
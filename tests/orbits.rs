@@ -0,0 +1,104 @@
+/*
+ * Checks the classes of configurations under the rotations and the
+ * complement, which the reduced searches and the grouping of counter
+ * examples rely on.
+ */
+
+use density_checker::{canonical_form, check_orbit, complement, orbit_size, rotate, same_orbit, Verdict};
+
+#[test]
+fn all_rotations_get_the_same_verdict() {
+    for size in 1..=10 {
+        for value in 0..1 << size {
+            let report = check_orbit(value, size);
+
+            assert_eq!(report.verdicts.len(), size as usize);
+            assert!(report.agrees(), "{value:#X} of size {size} : {:?}", report.distinct());
+        }
+    }
+}
+
+#[test]
+fn orbit_reports_list_the_verdict_of_each_rotation() {
+    let report = check_orbit(0b0010110, 7);
+
+    assert_eq!(report.verdicts, vec![Verdict::Correct; 7]);
+    assert_eq!(report.distinct(), vec![Verdict::Correct]);
+    assert_eq!(check_orbit(rotate(0b0010110, 7, 3), 7), report);
+}
+
+#[test]
+fn rotations_are_in_the_same_orbit() {
+    for value in [0b0011, 0b0110, 0b1100, 0b1001] {
+        assert!(same_orbit(0b0011, value, 4, false), "{value:#b}");
+    }
+    assert!(! same_orbit(0b0011, 0b0101, 4, false));
+    assert!(! same_orbit(0b0011, 0b0101, 4, true));
+}
+
+#[test]
+fn complements_are_only_in_the_same_orbit_when_asked() {
+    assert!(! same_orbit(0b0001, 0b1110, 4, false));
+    assert!(same_orbit(0b0001, 0b1110, 4, true));
+    assert!(same_orbit(0b0001, 0b0111, 4, true));
+    assert!(same_orbit(0b00111, 0b11100, 5, false));
+    assert!(! same_orbit(0b00111, 0b01000, 5, false));
+}
+
+#[test]
+fn orbits_group_the_configurations_into_necklaces() {
+    // OEIS A000031 and A000013 for sizes 1 to 8
+    let expected = [(2, 1), (3, 2), (4, 2), (6, 4), (8, 4), (14, 8), (20, 10), (36, 20)];
+
+    for (size, &(plain, with_complement)) in (1..).zip(&expected) {
+        let count = |with_complement : bool| {
+            let mut representatives : Vec<u32> = vec![];
+            for value in 0..1 << size {
+                if ! representatives.iter().any(|&r| same_orbit(r, value, size, with_complement)) {
+                    representatives.push(value);
+                }
+            }
+            representatives.len()
+        };
+
+        assert_eq!((count(false), count(true)), (plain, with_complement), "size {size}");
+    }
+}
+
+#[test]
+fn classes_share_their_canonical_form() {
+    for size in 1..=10 {
+        for value in 0..1 << size {
+            let form = canonical_form(value, size);
+
+            assert!(form <= value && same_orbit(value, form, size, true), "{value:#X} of size {size}");
+            for shift in 0..size {
+                let rotated = rotate(value, size, shift);
+                assert_eq!(canonical_form(rotated, size), form, "{value:#X} of size {size}");
+                assert_eq!(canonical_form(complement(rotated, size), size), form, "{value:#X} of size {size}");
+            }
+        }
+    }
+    assert_eq!(canonical_form(0b1110, 4), 0b0001);
+    assert_eq!(canonical_form(0b0110, 4), 0b0011);
+}
+
+#[test]
+fn orbit_sizes_count_the_members_of_the_classes() {
+    // the uniform configurations are each other's complements
+    assert_eq!(orbit_size(0, 4), 2);
+    // 0101 and 1010 are rotations and complements of each other
+    assert_eq!(orbit_size(0b0101, 4), 2);
+    // 0011, 0110, 1100 and 1001, their own complements
+    assert_eq!(orbit_size(0b0011, 4), 4);
+    // 0001 and its rotations, and their complements
+    assert_eq!(orbit_size(0b0001, 4), 8);
+
+    for size in 1..=8 {
+        for value in 0..1 << size {
+            let members = (0..1 << size).filter(|&other| canonical_form(other, size) == canonical_form(value, size));
+            assert_eq!(orbit_size(value, size) as usize, members.count(), "{value:#X} of size {size}");
+            assert_eq!(2 * size % orbit_size(value, size), 0, "{value:#X} of size {size}");
+        }
+    }
+}
@@ -0,0 +1,90 @@
+/*
+ * Checks the counts the searches report, with the options a library user
+ * would give them.
+ */
+#![cfg(feature = "search")]
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use density_checker::{
+    find_counter_example_at_density, search_configurations, search_size, DensityError, Reduction, SearchOptions,
+    SearchProgress, SearchStatus,
+};
+
+#[test]
+fn default_searches_count_every_configuration() {
+    for size in [2, 7, 12, 16] {
+        let outcome = search_configurations(size, &SearchOptions::default());
+
+        assert_eq!(outcome.counter_example, None, "size {size}");
+        assert_eq!(outcome.total, 1 << (size - 1), "size {size}");
+        assert_eq!(outcome.tested, outcome.total, "size {size}");
+        assert_eq!(outcome.skipped, 0, "size {size}");
+        assert!(! outcome.timed_out && ! outcome.interrupted, "size {size}");
+    }
+}
+
+#[test]
+fn reduced_searches_count_every_configuration() {
+    for reduction in [Reduction::Full, Reduction::Rotation, Reduction::Reflection] {
+        let options = SearchOptions { reduction, ..Default::default() };
+        let outcome = search_configurations(12, &options);
+
+        assert_eq!(outcome.counter_example, None, "{reduction:?}");
+        assert_eq!(outcome.tested, outcome.total, "{reduction:?}");
+    }
+}
+
+#[test]
+fn clean_searches_by_rotation_are_not_exhaustive() {
+    for (reduction, status) in [(Reduction::Complement, SearchStatus::Clean), (Reduction::Rotation, SearchStatus::Reduced),
+        (Reduction::Reflection, SearchStatus::Reduced)] {
+        let options = SearchOptions { reduction, ..Default::default() };
+        let mut output = vec![];
+
+        assert_eq!(search_size(10, &options, &mut output).unwrap(), status, "{reduction:?}");
+        assert_eq!(String::from_utf8(output).unwrap().contains("not exhaustive"), status == SearchStatus::Reduced,
+            "{reduction:?}");
+    }
+}
+
+#[test]
+fn densities_reject_ties_and_too_many_ones() {
+    assert_eq!(find_counter_example_at_density(10, 3), Ok(None));
+    assert_eq!(find_counter_example_at_density(10, 5), Err(DensityError::Tie));
+    assert_eq!(find_counter_example_at_density(10, 11), Err(DensityError::TooManyOnes { size : 10, ones : 11 }));
+}
+
+#[test]
+fn progress_ends_with_the_final_count() {
+    let calls = Arc::new(Mutex::new(vec![]));
+    let recorded = Arc::clone(&calls);
+    let options = SearchOptions {
+        progress : Some(Box::new(move |progress : &SearchProgress| {
+            recorded.lock().unwrap().push((progress.tested, progress.total));
+        })),
+        ..Default::default()
+    };
+
+    let outcome = search_configurations(16, &options);
+    let calls = calls.lock().unwrap();
+
+    assert_eq!(calls.first(), Some(&(0, 1 << 15)));
+    assert_eq!(calls.last(), Some(&(outcome.tested, 1 << 15)));
+    assert_eq!(outcome.tested, 1 << 15);
+    assert!(calls.len() > 2, "{} calls", calls.len());
+}
+
+#[test]
+fn searches_past_their_deadline_are_partial() {
+    let options = SearchOptions {
+        deadline : Some(Instant::now() - Duration::from_secs(1)),
+        ..Default::default()
+    };
+
+    let outcome = search_configurations(22, &options);
+
+    assert!(outcome.timed_out);
+    assert!(outcome.tested > 0 && outcome.tested < outcome.total, "{} of {}", outcome.tested, outcome.total);
+}